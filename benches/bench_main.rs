@@ -2,5 +2,6 @@ use criterion::criterion_main;
 mod benchmarks;
 
 criterion_main! {
-    benchmarks::sim_bench::benches
+    benchmarks::sim_bench::benches,
+    benchmarks::layout_bench::benches
 }
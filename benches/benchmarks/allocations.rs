@@ -0,0 +1,35 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// wraps the system allocator to count every allocation made by the benchmarks
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// returns the average number of allocations made by each of the `runs` calls to `f`
+pub fn allocations_per_run(runs: usize, mut f: impl FnMut()) -> f64 {
+    let start = ALLOCATIONS.load(Ordering::SeqCst);
+    for _ in 0..runs {
+        f();
+    }
+    (ALLOCATIONS.load(Ordering::SeqCst) - start) as f64 / runs as f64
+}
@@ -0,0 +1,53 @@
+use criterion::measurement::WallTime;
+use criterion::{black_box, criterion_group, BenchmarkGroup, BenchmarkId, Criterion};
+use ratatui::{buffer::Buffer, layout::Rect};
+use terminal_fluid_sim::render::{render_sim, RenderMode};
+use terminal_fluid_sim::{ColumnMajor, FluidSim, Layout, RowMajor, SimConfig, Tiled};
+
+/// a wide grid like the one of a big terminal
+const GRID_SIZE: (usize, usize) = (300, 100);
+
+/// a step is mostly the projection which walks the grid in storage order
+pub fn projection_layout_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step layout");
+    bench_step::<ColumnMajor>(&mut group, "column major");
+    bench_step::<RowMajor>(&mut group, "row major");
+    bench_step::<Tiled<16>>(&mut group, "tiled 16");
+    group.finish();
+}
+
+fn bench_step<L: Layout>(group: &mut BenchmarkGroup<WallTime>, name: &str) {
+    let (width, height) = GRID_SIZE;
+    let mut sim = FluidSim::<f32, L>::new(width, height, SimConfig::default());
+    group.bench_function(BenchmarkId::from_parameter(name), |b| {
+        b.iter(|| {
+            sim.next_step();
+        })
+    });
+}
+
+/// draws the sim with `render_sim` like the app does, one cell per half block
+pub fn render_layout_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render layout");
+    bench_render::<ColumnMajor>(&mut group, "column major");
+    bench_render::<RowMajor>(&mut group, "row major");
+    bench_render::<Tiled<16>>(&mut group, "tiled 16");
+    group.finish();
+}
+
+fn bench_render<L: Layout>(group: &mut BenchmarkGroup<WallTime>, name: &str) {
+    let (width, height) = GRID_SIZE;
+    let mut sim = FluidSim::<f32, L>::new(width, height, SimConfig::default());
+    sim.next_step();
+    // one simulation cell per half block
+    let area = Rect::new(0, 0, width as u16, (height / 2) as u16);
+    let mut buf = Buffer::empty(area);
+    group.bench_function(BenchmarkId::from_parameter(name), |b| {
+        b.iter(|| {
+            render_sim(&sim, RenderMode::Instant, area, &mut buf);
+            black_box(&buf);
+        })
+    });
+}
+
+criterion_group!(benches, projection_layout_bench, render_layout_bench);
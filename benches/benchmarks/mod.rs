@@ -1 +1,3 @@
+pub mod allocations;
+pub mod layout_bench;
 pub mod sim_bench;
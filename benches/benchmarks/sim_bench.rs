@@ -1,14 +1,89 @@
-use criterion::{criterion_group, Criterion};
+use criterion::{criterion_group, BenchmarkId, Criterion};
 use terminal_fluid_sim::{FluidSim, SimConfig};
 
+use super::allocations::allocations_per_run;
+
+/// the grid sizes the step benchmarks are run at
+const GRID_SIZES: [(usize, usize); 3] = [(50, 50), (100, 100), (200, 100)];
+
+/// the fixed time step of the benchmarks in seconds, so every run simulates the same steps
+const DELTA: f32 = 0.01;
+
+/// a step reuses its buffers, only the thread pool allocates now and then
+const MAX_ALLOCATIONS_PER_STEP: f64 = 1.0;
+
 pub fn sim_bench(c: &mut Criterion) {
     let config = SimConfig::default();
-    let mut sim = FluidSim::new(100, 100, config);
+    let mut sim = FluidSim::<f32>::new(100, 100, config);
     c.bench_function("simulate steps", |b| {
         b.iter(|| {
-            sim.next_step();
+            sim.step(DELTA);
         })
     });
 }
 
-criterion_group!(benches, sim_bench);
+pub fn sim_bench_f64(c: &mut Criterion) {
+    let config = SimConfig::default();
+    let mut sim = FluidSim::<f64>::new(100, 100, config);
+    c.bench_function("simulate steps f64", |b| {
+        b.iter(|| {
+            sim.step(f64::from(DELTA));
+        })
+    });
+}
+
+/// times a step at several grid sizes and checks that a step does not allocate its fields
+pub fn allocations_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step allocations");
+    for (width, height) in GRID_SIZES {
+        let mut sim = FluidSim::<f32>::new(width, height, SimConfig::default());
+        // the first step warms up the thread pool
+        sim.step(DELTA);
+        let allocations = allocations_per_run(100, || {
+            sim.step(DELTA);
+        });
+        assert!(
+            allocations < MAX_ALLOCATIONS_PER_STEP,
+            "{allocations:.2} allocations per step at {width}x{height}"
+        );
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{width}x{height}")),
+            &(width, height),
+            |b, _| {
+                b.iter(|| {
+                    sim.step(DELTA);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// times a step with the vectorized kernels next to the scalar loops on the same grids
+#[cfg(feature = "simd")]
+pub fn simd_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simd against scalar");
+    for (width, height) in GRID_SIZES {
+        for vectorized in [true, false] {
+            let mut sim = FluidSim::<f32>::new(width, height, SimConfig::default());
+            sim.set_vectorized(vectorized);
+            let name = if vectorized { "simd" } else { "scalar" };
+            group.bench_function(BenchmarkId::new(name, format!("{width}x{height}")), |b| {
+                b.iter(|| sim.step(DELTA))
+            });
+        }
+    }
+    group.finish();
+}
+
+#[cfg(not(feature = "simd"))]
+criterion_group!(benches, sim_bench, sim_bench_f64, allocations_bench);
+#[cfg(feature = "simd")]
+criterion_group!(
+    benches,
+    sim_bench,
+    sim_bench_f64,
+    allocations_bench,
+    simd_bench
+);
@@ -3,28 +3,55 @@ use std::time::Instant;
 // use color_eyre::Result;
 use ratatui::prelude::*;
 
-use terminal_fluid_sim::FluidSim;
+use terminal_fluid_sim::{FluidSim, Solver};
 
-use crate::{handler::handle_events, ui::render_app, Result};
+use crate::{
+    handler::{handle_events, handle_resize},
+    ui::{render_app, RenderMode},
+    Result,
+};
 
-use super::{config::AppConfig, info::AppInfo};
+use super::{config::AppConfig, diagnostics::DiagnosticsHistory, info::AppInfo};
 
-#[derive(Default)]
 pub struct App {
     /// The current state of the app (running or quit)
     pub state: AppState,
 
-    /// the actual sim
-    pub fluid_sim: FluidSim,
+    /// the actual sim, any solver can be swapped in
+    pub sim: Box<dyn Solver>,
 
     /// relevant information about the app
     pub info: AppInfo,
 
+    /// the diagnostics of the last steps of the sim
+    pub diagnostics: DiagnosticsHistory,
+
     /// information needed by the editor
     pub editor_info: EditorInfo,
 
     /// configuration settings for the app
     pub config: AppConfig,
+
+    /// which fields of the sim are shown
+    pub render_mode: RenderMode,
+
+    /// draws the streamlines of the sim over its fields
+    pub show_streamlines: bool,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        App {
+            state: AppState::default(),
+            sim: Box::new(FluidSim::default()),
+            info: AppInfo::default(),
+            diagnostics: DiagnosticsHistory::default(),
+            editor_info: EditorInfo::default(),
+            config: AppConfig::default(),
+            render_mode: RenderMode::default(),
+            show_streamlines: false,
+        }
+    }
 }
 
 #[derive(Default, Clone, PartialEq)]
@@ -48,11 +75,8 @@ impl App {
     ///
     /// This is the main event loop for the app.
     pub fn run(&mut self, mut terminal: Terminal<impl Backend>) -> Result<()> {
-        let initial_size = terminal.size().unwrap();
-        self.fluid_sim.resize(
-            initial_size.width as usize,
-            (initial_size.height * 2) as usize,
-        );
+        let initial_size = terminal.size()?;
+        handle_resize(self, initial_size.width, initial_size.height);
         while self.is_running() {
             handle_events(self)?;
             terminal.draw(|frame| {
@@ -62,6 +86,32 @@ impl App {
         Ok(())
     }
 
+    /// replaces the solver by a new one of the same size, with the same blocks
+    pub fn set_solver(&mut self, mut solver: Box<dyn Solver>) {
+        let blocks = self.sim.get_block_grid();
+        for (x, y) in blocks.positions() {
+            if blocks[(x, y)] {
+                solver.set_block(x, y);
+            } else {
+                solver.unset_block(x, y);
+            }
+        }
+        // the new solver starts around its blocks
+        solver.restart_sim();
+
+        self.sim = solver;
+        self.diagnostics.clear();
+        self.editor_info.selected_obstacle = None;
+        self.render_mode = RenderMode::Instant;
+    }
+
+    /// the heaters and coolers only act on the smoke and fire modes of the grid
+    pub fn simulates_heat(&self) -> bool {
+        self.sim
+            .as_fluid_sim()
+            .is_some_and(FluidSim::simulates_heat)
+    }
+
     const fn is_running(&self) -> bool {
         matches!(self.state, AppState::Running | AppState::Editing)
     }
@@ -74,7 +124,8 @@ impl App {
                 if self.info.can_update() {
                     self.measure_and_update(frame);
                 } else {
-                    self.fluid_sim.next_step();
+                    let diagnostics = self.sim.next_step();
+                    self.diagnostics.push(diagnostics);
                     self.editor_info.editor_area =
                         render_app(self, frame.size(), frame.buffer_mut());
                 }
@@ -89,17 +140,21 @@ impl App {
     fn measure_and_update(&mut self, frame: &mut Frame) {
         // measure the simulation time and save the info
         let start = Instant::now();
-        self.fluid_sim.next_step();
+        let diagnostics = self.sim.next_step();
         let sim_duration = start.elapsed();
+        self.diagnostics.push(diagnostics);
 
         // measure rendering time
         let start = Instant::now();
         self.editor_info.editor_area = render_app(self, frame.size(), frame.buffer_mut());
         let render_duration = start.elapsed();
 
-        let (width, height) = self.fluid_sim.get_size();
-        self.info
-            .update(sim_duration, render_duration, width, height);
+        self.info.update(
+            sim_duration,
+            render_duration,
+            self.sim.as_ref(),
+            self.editor_info.selected_obstacle.as_ref(),
+        );
     }
 }
 
@@ -107,4 +162,103 @@ impl App {
 pub struct EditorInfo {
     pub last_mouse_pos: Option<(u16, u16)>,
     pub editor_area: Rect,
+
+    /// what the mouse does in the editor
+    pub tool: EditorTool,
+
+    /// the obstacle whose forces are shown in the sim info
+    pub selected_obstacle: Option<SelectedObstacle>,
+}
+
+impl EditorInfo {
+    /// selects the obstacle holding the block `(x, y)`, nothing is selected
+    /// when the solver cannot measure the forces
+    pub fn select_obstacle(&mut self, sim: &dyn Solver, (x, y): (usize, usize)) {
+        self.selected_obstacle = sim
+            .as_fluid_sim()
+            .map(|sim| SelectedObstacle {
+                block: (x, y),
+                blocks: sim.obstacle_at(x, y),
+            })
+            .filter(|obstacle| !obstacle.blocks.is_empty());
+    }
+
+    /// finds the blocks of the selected obstacle again after the blocks were edited
+    pub fn refresh_obstacle(&mut self, sim: &dyn Solver) {
+        if let Some(block) = self
+            .selected_obstacle
+            .as_ref()
+            .map(|obstacle| obstacle.block)
+        {
+            self.select_obstacle(sim, block);
+        }
+    }
+}
+
+/// an obstacle picked with the select tool
+///
+/// its blocks are searched once when it is selected instead of every frame
+pub struct SelectedObstacle {
+    /// the block that was clicked
+    pub block: (usize, usize),
+
+    /// every block connected to the clicked one
+    pub blocks: Vec<(usize, usize)>,
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum EditorTool {
+    /// adds and removes blocks
+    #[default]
+    Draw,
+
+    /// selects an obstacle to measure the forces on it
+    Select,
+
+    /// adds and removes probes
+    Probe,
+
+    /// adds and removes tracer emitters
+    Emitter,
+
+    /// paints and removes cells holding the fluid hot
+    Heater,
+
+    /// paints and removes cells holding the fluid cold
+    Cooler,
+}
+
+impl EditorTool {
+    /// the tool after this one, the heaters and coolers are skipped when the sim has no heat
+    pub fn next(self, with_heat: bool) -> Self {
+        let next = match self {
+            EditorTool::Draw => EditorTool::Select,
+            EditorTool::Select => EditorTool::Probe,
+            EditorTool::Probe => EditorTool::Emitter,
+            EditorTool::Emitter => EditorTool::Heater,
+            EditorTool::Heater => EditorTool::Cooler,
+            EditorTool::Cooler => EditorTool::Draw,
+        };
+        if !with_heat && next.is_heat_source() {
+            EditorTool::Draw
+        } else {
+            next
+        }
+    }
+
+    #[inline]
+    pub fn is_heat_source(self) -> bool {
+        matches!(self, EditorTool::Heater | EditorTool::Cooler)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EditorTool::Draw => "Draw",
+            EditorTool::Select => "Select",
+            EditorTool::Probe => "Probe",
+            EditorTool::Emitter => "Emitter",
+            EditorTool::Heater => "Heater",
+            EditorTool::Cooler => "Cooler",
+        }
+    }
 }
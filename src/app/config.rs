@@ -4,13 +4,116 @@ use ratatui::widgets::{HighlightSpacing, Row, StatefulWidget, Table, TableState,
 
 use crate::ui::THEME;
 
-use terminal_fluid_sim::SimConfig;
+use terminal_fluid_sim::{
+    FluidSim, LatticeBoltzmann, ShallowWater, SimConfig, SimMode, Solver, Sph, STANDARD_GRAVITY,
+};
+
+/// the number of rows in the settings table
+const SETTINGS_COUNT: usize = 20;
+
+/// the coldest temperature the walls can hold in K above the ambient one
+const MIN_WALL_TEMPERATURE: f32 = -500.0;
+
+/// how big the simulation grid is compared to the area it is rendered in
+#[derive(Clone, Copy, PartialEq)]
+pub enum SimResolution {
+    /// scales the view size where 1.0 is one simulation cell per half block
+    Scale(f32),
+
+    /// a fixed grid size that does not follow the terminal size
+    Fixed { width: usize, height: usize },
+}
+
+impl Default for SimResolution {
+    fn default() -> Self {
+        SimResolution::Scale(1.0)
+    }
+}
+
+impl SimResolution {
+    /// the smallest grid the simulator can handle since it keeps a border around it
+    const MIN_SIZE: usize = 3;
+
+    /// returns the size of the simulation grid for a view measured in half blocks
+    pub fn sim_size(&self, view_width: usize, view_height: usize) -> (usize, usize) {
+        let (width, height) = match *self {
+            SimResolution::Scale(scale) => (
+                (view_width as f32 * scale).round() as usize,
+                (view_height as f32 * scale).round() as usize,
+            ),
+            SimResolution::Fixed { width, height } => (width, height),
+        };
+        (width.max(Self::MIN_SIZE), height.max(Self::MIN_SIZE))
+    }
+}
+
+/// the solvers the app can switch between
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum SolverKind {
+    /// the staggered grid of [`FluidSim`] with its smoke and liquid modes
+    #[default]
+    Grid,
+
+    /// the smoke of [`LatticeBoltzmann`] in a channel with walls at the top and bottom
+    LatticeBoltzmann,
+
+    /// the height field of [`ShallowWater`]
+    ShallowWater,
+
+    /// the particles of [`Sph`]
+    Sph,
+}
+
+impl SolverKind {
+    fn next(self) -> Self {
+        match self {
+            SolverKind::Grid => SolverKind::LatticeBoltzmann,
+            SolverKind::LatticeBoltzmann => SolverKind::ShallowWater,
+            SolverKind::ShallowWater => SolverKind::Sph,
+            SolverKind::Sph => SolverKind::Grid,
+        }
+    }
+
+    fn previous(self) -> Self {
+        match self {
+            SolverKind::Grid => SolverKind::Sph,
+            SolverKind::LatticeBoltzmann => SolverKind::Grid,
+            SolverKind::ShallowWater => SolverKind::LatticeBoltzmann,
+            SolverKind::Sph => SolverKind::ShallowWater,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SolverKind::Grid => "Grid",
+            SolverKind::LatticeBoltzmann => "LBM",
+            SolverKind::ShallowWater => "Shallow",
+            SolverKind::Sph => "SPH",
+        }
+    }
+
+    /// creates a solver of this kind with the given size
+    pub fn create(self, width: usize, height: usize, config: SimConfig) -> Box<dyn Solver> {
+        match self {
+            SolverKind::Grid => Box::new(FluidSim::new(width, height, config)),
+            SolverKind::LatticeBoltzmann => Box::new(LatticeBoltzmann::new(width, height, config)),
+            SolverKind::ShallowWater => Box::new(ShallowWater::new(width, height, config)),
+            SolverKind::Sph => Box::new(Sph::new(width, height, config)),
+        }
+    }
+}
 
 #[derive(Default)]
 pub struct AppConfig {
     /// the configuration of the sim
     config: SimConfig,
 
+    /// the resolution of the sim compared to the view
+    resolution: SimResolution,
+
+    /// which solver runs the sim
+    solver: SolverKind,
+
     /// the current selection to be changed
     current_selection: TableState,
 }
@@ -18,22 +121,42 @@ pub struct AppConfig {
 impl AppConfig {
     #[inline]
     fn add_gravity(&mut self) {
-        self.config.gravity += 0.1;
+        self.config.gravity.1 += 0.1;
     }
 
     #[inline]
     fn reduce_gravity(&mut self) {
-        self.config.gravity -= 0.1;
+        self.config.gravity.1 -= 0.1;
+    }
+
+    #[inline]
+    fn add_horizontal_gravity(&mut self) {
+        self.config.gravity.0 += 0.1;
+    }
+
+    #[inline]
+    fn reduce_horizontal_gravity(&mut self) {
+        self.config.gravity.0 -= 0.1;
+    }
+
+    #[inline]
+    fn add_rotation_rate(&mut self) {
+        self.config.rotation_rate += 0.1;
+    }
+
+    #[inline]
+    fn reduce_rotation_rate(&mut self) {
+        self.config.rotation_rate -= 0.1;
     }
 
     #[inline]
     fn add_wind_speed(&mut self) {
-        self.config.wind_speed += 1.0;
+        self.config.wind_speed += 0.05;
     }
 
     #[inline]
     fn reduce_wind_speed(&mut self) {
-        self.config.wind_speed = (self.config.wind_speed - 1.0).max(0.0);
+        self.config.wind_speed = (self.config.wind_speed - 0.05).max(0.0);
     }
 
     #[inline]
@@ -53,11 +176,187 @@ impl AppConfig {
 
     #[inline]
     fn reduce_density(&mut self) {
-        self.config.density -= 25.0;
+        self.config.density = (self.config.density - 25.0).max(25.0);
+    }
+
+    #[inline]
+    fn add_cell_size(&mut self) {
+        self.config.cell_size += 0.001;
+    }
+
+    #[inline]
+    fn reduce_cell_size(&mut self) {
+        self.config.cell_size = (self.config.cell_size - 0.001).max(0.001);
+    }
+
+    #[inline]
+    fn add_viscosity(&mut self) {
+        self.config.viscosity = if self.config.viscosity == 0.0 {
+            1.0e-7
+        } else {
+            self.config.viscosity * 10.0
+        };
+    }
+
+    #[inline]
+    fn reduce_viscosity(&mut self) {
+        self.config.viscosity = if self.config.viscosity <= 1.0e-7 {
+            0.0
+        } else {
+            self.config.viscosity / 10.0
+        };
+    }
+
+    #[inline]
+    fn add_surface_tension(&mut self) {
+        self.config.surface_tension = if self.config.surface_tension == 0.0 {
+            1.0e-3
+        } else {
+            self.config.surface_tension * 2.0
+        };
+    }
+
+    #[inline]
+    fn reduce_surface_tension(&mut self) {
+        self.config.surface_tension = if self.config.surface_tension <= 1.0e-3 {
+            0.0
+        } else {
+            self.config.surface_tension / 2.0
+        };
+    }
+
+    #[inline]
+    fn add_heater_temperature(&mut self) {
+        self.config.heater_temperature += 50.0;
+    }
+
+    #[inline]
+    fn reduce_heater_temperature(&mut self) {
+        self.config.heater_temperature = (self.config.heater_temperature - 50.0).max(0.0);
+    }
+
+    /// the walls start at the ambient temperature when they begin to conduct
+    #[inline]
+    fn add_wall_temperature(&mut self) {
+        self.config.wall_temperature = Some(match self.config.wall_temperature {
+            None => 0.0,
+            Some(temperature) => temperature + 100.0,
+        });
     }
 
+    /// the walls stop holding a temperature below the coldest one
     #[inline]
-    pub fn get_gravity(&self) -> f32 {
+    fn reduce_wall_temperature(&mut self) {
+        self.config.wall_temperature = self
+            .config
+            .wall_temperature
+            .map(|temperature| temperature - 100.0)
+            .filter(|&temperature| temperature >= MIN_WALL_TEMPERATURE);
+    }
+
+    #[inline]
+    fn add_resolution(&mut self) {
+        self.resolution = match self.resolution {
+            SimResolution::Scale(scale) => SimResolution::Scale((scale + 0.25).min(4.0)),
+            fixed => fixed,
+        };
+    }
+
+    #[inline]
+    fn reduce_resolution(&mut self) {
+        self.resolution = match self.resolution {
+            SimResolution::Scale(scale) => SimResolution::Scale((scale - 0.25).max(0.25)),
+            fixed => fixed,
+        };
+    }
+
+    /// switches between the smoke, the liquid, the fire and the two phases, the liquid
+    /// and the heavy phase fall down so they start with the gravity of the earth if there was none
+    fn switch_mode(&mut self) {
+        self.config.mode = match self.config.mode {
+            SimMode::Smoke => SimMode::Liquid,
+            SimMode::Liquid => SimMode::Fire,
+            SimMode::Fire => SimMode::TwoPhase,
+            SimMode::TwoPhase => SimMode::Smoke,
+        };
+        let needs_gravity = matches!(self.config.mode, SimMode::Liquid | SimMode::TwoPhase);
+        if needs_gravity && self.config.gravity == (0.0, 0.0) {
+            self.config.gravity = (0.0, -STANDARD_GRAVITY);
+        }
+    }
+
+    #[inline]
+    fn add_flip_ratio(&mut self) {
+        self.config.flip_ratio = (self.config.flip_ratio + 0.05).min(1.0);
+    }
+
+    #[inline]
+    fn reduce_flip_ratio(&mut self) {
+        self.config.flip_ratio = (self.config.flip_ratio - 0.05).max(0.0);
+    }
+
+    #[inline]
+    fn add_ignition_temperature(&mut self) {
+        self.config.ignition_temperature += 25.0;
+    }
+
+    #[inline]
+    fn reduce_ignition_temperature(&mut self) {
+        self.config.ignition_temperature = (self.config.ignition_temperature - 25.0).max(0.0);
+    }
+
+    #[inline]
+    fn add_burn_rate(&mut self) {
+        self.config.burn_rate += 0.5;
+    }
+
+    #[inline]
+    fn reduce_burn_rate(&mut self) {
+        self.config.burn_rate = (self.config.burn_rate - 0.5).max(0.0);
+    }
+
+    #[inline]
+    fn add_buoyancy(&mut self) {
+        self.config.buoyancy += 0.0005;
+    }
+
+    #[inline]
+    fn reduce_buoyancy(&mut self) {
+        self.config.buoyancy = (self.config.buoyancy - 0.0005).max(0.0);
+    }
+
+    #[inline]
+    fn add_cooling_rate(&mut self) {
+        self.config.cooling_rate += 0.25;
+    }
+
+    #[inline]
+    fn reduce_cooling_rate(&mut self) {
+        self.config.cooling_rate = (self.config.cooling_rate - 0.25).max(0.0);
+    }
+
+    #[inline]
+    fn add_second_density(&mut self) {
+        self.config.second_density += 25.0;
+    }
+
+    #[inline]
+    fn reduce_second_density(&mut self) {
+        self.config.second_density = (self.config.second_density - 25.0).max(25.0);
+    }
+
+    #[inline]
+    fn add_solver(&mut self) {
+        self.solver = self.solver.next();
+    }
+
+    #[inline]
+    fn reduce_solver(&mut self) {
+        self.solver = self.solver.previous();
+    }
+
+    #[inline]
+    pub fn get_gravity(&self) -> (f32, f32) {
         self.config.gravity
     }
 
@@ -76,6 +375,20 @@ impl AppConfig {
         self.config.density
     }
 
+    #[inline]
+    pub fn get_resolution(&self) -> SimResolution {
+        self.resolution
+    }
+
+    pub fn set_resolution(&mut self, resolution: SimResolution) {
+        self.resolution = resolution;
+    }
+
+    #[inline]
+    pub fn get_solver(&self) -> SolverKind {
+        self.solver
+    }
+
     pub fn get_config(&self) -> SimConfig {
         self.config.clone()
     }
@@ -87,6 +400,22 @@ impl AppConfig {
                 1 => self.reduce_wind_speed(),
                 2 => self.reduce_smoke_size(),
                 3 => self.reduce_density(),
+                4 => self.reduce_cell_size(),
+                5 => self.reduce_viscosity(),
+                6 => self.reduce_resolution(),
+                7 => self.switch_mode(),
+                8 => self.reduce_flip_ratio(),
+                9 => self.reduce_solver(),
+                10 => self.reduce_ignition_temperature(),
+                11 => self.reduce_burn_rate(),
+                12 => self.reduce_buoyancy(),
+                13 => self.reduce_cooling_rate(),
+                14 => self.reduce_second_density(),
+                15 => self.reduce_horizontal_gravity(),
+                16 => self.reduce_rotation_rate(),
+                17 => self.reduce_surface_tension(),
+                18 => self.reduce_heater_temperature(),
+                19 => self.reduce_wall_temperature(),
                 _ => {}
             }
         }
@@ -99,6 +428,22 @@ impl AppConfig {
                 1 => self.add_wind_speed(),
                 2 => self.add_smoke_size(),
                 3 => self.add_density(),
+                4 => self.add_cell_size(),
+                5 => self.add_viscosity(),
+                6 => self.add_resolution(),
+                7 => self.switch_mode(),
+                8 => self.add_flip_ratio(),
+                9 => self.add_solver(),
+                10 => self.add_ignition_temperature(),
+                11 => self.add_burn_rate(),
+                12 => self.add_buoyancy(),
+                13 => self.add_cooling_rate(),
+                14 => self.add_second_density(),
+                15 => self.add_horizontal_gravity(),
+                16 => self.add_rotation_rate(),
+                17 => self.add_surface_tension(),
+                18 => self.add_heater_temperature(),
+                19 => self.add_wall_temperature(),
                 _ => {}
             }
         }
@@ -107,7 +452,7 @@ impl AppConfig {
     pub fn down_select(&mut self) {
         let i = match self.current_selection.selected() {
             Some(i) => {
-                if i >= 3 {
+                if i >= SETTINGS_COUNT - 1 {
                     0
                 } else {
                     i + 1
@@ -123,7 +468,7 @@ impl AppConfig {
         let i = match self.current_selection.selected() {
             Some(i) => {
                 if i == 0 {
-                    3
+                    SETTINGS_COUNT - 1
                 } else {
                     i - 1
                 }
@@ -137,19 +482,89 @@ impl AppConfig {
 
 impl Widget for &mut AppConfig {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let gravity = self.config.gravity;
+        let (horizontal_gravity, gravity) = self.config.gravity;
+        let rotation_rate = self.config.rotation_rate;
+        let surface_tension = self.config.surface_tension;
+        let heater_temperature = self.config.heater_temperature;
+        let wall_temperature = self
+            .config
+            .wall_temperature
+            .map_or("Off".to_string(), |temperature| {
+                format!("{temperature:.0} ΔK")
+            });
         let wind_speed = self.config.wind_speed;
         let smoke_size = self.config.smoke_size * 100.0; // this is a precentage
         let density = self.config.density;
+        let cell_size = self.config.cell_size * 100.0; // shown in centimeters
+        let viscosity = self.config.viscosity;
+        let mode = match self.config.mode {
+            SimMode::Smoke => "Smoke",
+            SimMode::Liquid => "Liquid",
+            SimMode::Fire => "Fire",
+            SimMode::TwoPhase => "Two Phase",
+        };
+        let flip_ratio = self.config.flip_ratio * 100.0; // this is a precentage
+        let ignition_temperature = self.config.ignition_temperature;
+        let burn_rate = self.config.burn_rate;
+        let buoyancy = self.config.buoyancy * 1000.0; // shown in mm/s² per K
+        let cooling_rate = self.config.cooling_rate;
+        let second_density = self.config.second_density;
+        let resolution = match self.resolution {
+            SimResolution::Scale(scale) => format!("{:.0} %", scale * 100.0),
+            SimResolution::Fixed { width, height } => format!("{width}x{height}"),
+        };
 
         let rows = [
-            Row::new(vec![format!("{gravity:.1} m/s²"), "Gravity".to_string()]),
+            Row::new(vec![format!("{gravity:.1} m/s²"), "Gravity Y".to_string()]),
             Row::new(vec![
-                format!("{wind_speed:.0} m/s"),
+                format!("{wind_speed:.2} m/s"),
                 "Wind Speed".to_string(),
             ]),
             Row::new(vec![format!("{smoke_size:.0} %"), "Smoke Size".to_string()]),
-            Row::new(vec![format!("{density:.0}"), "Density".to_string()]),
+            Row::new(vec![format!("{density:.0} kg/m³"), "Density".to_string()]),
+            Row::new(vec![format!("{cell_size:.1} cm"), "Cell Size".to_string()]),
+            Row::new(vec![
+                format!("{viscosity:.0e} m²/s"),
+                "Viscosity".to_string(),
+            ]),
+            Row::new(vec![resolution, "Resolution".to_string()]),
+            Row::new(vec![mode.to_string(), "Mode".to_string()]),
+            Row::new(vec![format!("{flip_ratio:.0} %"), "FLIP Ratio".to_string()]),
+            Row::new(vec![self.solver.name().to_string(), "Solver".to_string()]),
+            Row::new(vec![
+                format!("{ignition_temperature:.0} ΔK"),
+                "Ignition".to_string(),
+            ]),
+            Row::new(vec![format!("{burn_rate:.1} 1/s"), "Burn Rate".to_string()]),
+            Row::new(vec![
+                format!("{buoyancy:.1} mm/s²/K"),
+                "Buoyancy".to_string(),
+            ]),
+            Row::new(vec![
+                format!("{cooling_rate:.2} 1/s"),
+                "Cooling".to_string(),
+            ]),
+            Row::new(vec![
+                format!("{second_density:.0} kg/m³"),
+                "Density 2".to_string(),
+            ]),
+            Row::new(vec![
+                format!("{horizontal_gravity:.1} m/s²"),
+                "Gravity X".to_string(),
+            ]),
+            Row::new(vec![
+                format!("{rotation_rate:.1} rad/s"),
+                "Rotation".to_string(),
+            ]),
+            Row::new(vec![
+                format!("{surface_tension:.3} N/m"),
+                "Tension".to_string(),
+            ]),
+            Row::new(vec![
+                format!("{heater_temperature:.0} ΔK"),
+                "Heaters".to_string(),
+            ]),
+            Row::new(vec![wall_temperature, "Wall Temp".to_string()]),
         ];
 
         let table = Table::new(rows, [Constraint::Fill(1), Constraint::Length(10)])
@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+
+use ratatui::layout::Constraint::*;
+use ratatui::prelude::{Buffer, Layout, Rect};
+use ratatui::widgets::{Sparkline, Widget};
+
+use terminal_fluid_sim::StepDiagnostics;
+
+use crate::ui::{render_left_right_text, THEME};
+
+/// the diagnostics of the last steps of the sim
+#[derive(Default)]
+pub struct DiagnosticsHistory {
+    history: VecDeque<StepDiagnostics>,
+}
+
+impl DiagnosticsHistory {
+    /// the number of steps kept, more than the width of the graphs
+    const CAPACITY: usize = 128;
+
+    pub fn push(&mut self, diagnostics: StepDiagnostics) {
+        if self.history.len() == Self::CAPACITY {
+            self.history.pop_front();
+        }
+        self.history.push_back(diagnostics);
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    /// the last values of a diagnostic that fit in a graph of the given width
+    fn last_values(&self, width: u16, field: fn(&StepDiagnostics) -> f32) -> Vec<f32> {
+        let skipped = self.history.len().saturating_sub(width as usize);
+        self.history.iter().skip(skipped).map(field).collect()
+    }
+}
+
+impl Widget for &DiagnosticsHistory {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let latest = self.history.back().copied().unwrap_or_default();
+        let width = area.width;
+
+        // a row for the latest value and one for the graph of every diagnostic
+        let areas = Layout::vertical([Length(1); 10]).split(area);
+        let rows = [
+            (
+                format!("{:.4} m²", latest.smoke_mass),
+                "Smoke",
+                self.last_values(width, |d| d.smoke_mass),
+            ),
+            (
+                format!("{:.2} J/m", latest.kinetic_energy),
+                "Energy",
+                self.last_values(width, |d| d.kinetic_energy),
+            ),
+            (
+                format!("{:.2} m²/s²", latest.enstrophy),
+                "Enstrophy",
+                self.last_values(width, |d| d.enstrophy),
+            ),
+            (
+                format!("{:.2} 1/s", latest.max_divergence),
+                "Max div.",
+                self.last_values(width, |d| d.max_divergence),
+            ),
+            (
+                format!("{:.2} m/s", latest.max_speed),
+                "Max speed",
+                self.last_values(width, |d| d.max_speed),
+            ),
+        ];
+
+        for (i, (value, name, values)) in rows.into_iter().enumerate() {
+            render_left_right_text(&[(value, name.to_string())], areas[i * 2], buf);
+            render_graph(&values, areas[i * 2 + 1], buf);
+        }
+    }
+}
+
+/// draws the values as a sparkline scaled to the largest one
+fn render_graph(values: &[f32], area: Rect, buf: &mut Buffer) {
+    let max = values.iter().copied().fold(0.0, f32::max);
+    // the sparkline only takes integers
+    let data: Vec<u64> = values
+        .iter()
+        .map(|value| {
+            if max > 0.0 {
+                (value / max * 100.0) as u64
+            } else {
+                0
+            }
+        })
+        .collect();
+    Sparkline::default()
+        .data(&data)
+        .max(100)
+        .style(THEME.text)
+        .render(area, buf);
+}
@@ -1,18 +1,36 @@
+use std::io;
 use std::time::{Duration, Instant};
 
 use ratatui::prelude::{Buffer, Rect};
 use ratatui::widgets::Widget;
 
+use terminal_fluid_sim::{ObstacleForces, Probe, Solver};
+
 use crate::ui::render_left_right_text;
 
+use super::SelectedObstacle;
+
 pub struct AppInfo {
     rendering_duration: Duration,
     simulation_step_duration: Duration,
     last_update: Instant,
     frame_count: usize,
     fps: f32,
+    solver_name: &'static str,
     width: usize,
     height: usize,
+    domain_size: (f32, f32),
+    reynolds_number: f32,
+
+    /// the viscosity the solver simulates when it is higher than the configured one
+    effective_viscosity: Option<f32>,
+    probe_count: usize,
+    obstacle_forces: Option<ObstacleForces>,
+    shedding_frequency: Option<f32>,
+    strouhal_number: Option<f32>,
+
+    /// the outcome of the last export of the probes, kept until the next one
+    probe_export: Option<String>,
 }
 
 impl Default for AppInfo {
@@ -23,8 +41,17 @@ impl Default for AppInfo {
             last_update: Instant::now(),
             frame_count: 0,
             fps: 0.0,
+            solver_name: "",
             width: 0,
             height: 0,
+            domain_size: (0.0, 0.0),
+            reynolds_number: 0.0,
+            effective_viscosity: None,
+            probe_count: 0,
+            obstacle_forces: None,
+            shedding_frequency: None,
+            strouhal_number: None,
+            probe_export: None,
         }
     }
 }
@@ -38,6 +65,14 @@ impl AppInfo {
         self.last_update.elapsed() > Duration::from_secs_f32(0.5)
     }
 
+    /// shows whether the probes could be written to `path`
+    pub fn set_probe_export(&mut self, path: &str, result: io::Result<()>) {
+        self.probe_export = Some(match result {
+            Ok(()) => format!("Saved {path}"),
+            Err(error) => format!("Failed: {}", error.kind()),
+        });
+    }
+
     fn calculate_fps(&mut self) {
         let elapsed = self.last_update.elapsed();
         self.fps = self.frame_count as f32 / elapsed.as_secs_f32();
@@ -47,24 +82,51 @@ impl AppInfo {
         &mut self,
         simulation_time: Duration,
         rendering_time: Duration,
-        width: usize,
-        height: usize,
+        sim: &dyn Solver,
+        selected_obstacle: Option<&SelectedObstacle>,
     ) {
+        let (width, height) = sim.get_size();
         self.simulation_step_duration = simulation_time;
         self.rendering_duration = rendering_time;
         self.calculate_fps();
         self.last_update = Instant::now();
         self.frame_count = 0;
+        self.solver_name = sim.name();
         self.width = width;
         self.height = height;
+        self.domain_size = sim.get_domain_size();
+        self.reynolds_number = sim.reynolds_number();
+        let viscosity = sim.effective_viscosity();
+        self.effective_viscosity = (viscosity > sim.get_config().viscosity).then_some(viscosity);
+
+        // the measurements need the staggered grid
+        let Some(sim) = sim.as_fluid_sim() else {
+            self.probe_count = 0;
+            self.obstacle_forces = None;
+            self.shedding_frequency = None;
+            self.strouhal_number = None;
+            return;
+        };
+        self.probe_count = sim.get_probes().len();
+
+        let obstacle = selected_obstacle.map(|obstacle| obstacle.blocks.as_slice());
+        self.obstacle_forces = obstacle.map(|obstacle| sim.obstacle_forces(obstacle));
+
+        // the shedding is measured with the first probe, which should be placed behind the obstacle
+        self.shedding_frequency = sim.get_probes().first().and_then(Probe::vertical_frequency);
+        self.strouhal_number = self
+            .shedding_frequency
+            .zip(obstacle)
+            .map(|(frequency, obstacle)| sim.strouhal_number(frequency, obstacle));
     }
 }
 
 impl Widget for &AppInfo {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let (width, height) = (self.width, self.height);
+        let (domain_width, domain_height) = self.domain_size;
 
-        let infos = [
+        let mut infos = vec![
             (
                 format_duration(self.simulation_step_duration),
                 "Simulation time".to_string(),
@@ -74,8 +136,47 @@ impl Widget for &AppInfo {
                 "Rendering time".to_string(),
             ),
             (format!("{:.1} fps", self.fps), "Frames".to_string()),
+            (self.solver_name.to_string(), "Solver".to_string()),
             (format!("x: {width}, y: {height}"), "Grid Size".to_string()),
+            (
+                format!("{domain_width:.2}x{domain_height:.2} m"),
+                "Domain".to_string(),
+            ),
+            (
+                format!("{:.0}", self.reynolds_number),
+                "Reynolds".to_string(),
+            ),
         ];
+        if let Some(viscosity) = self.effective_viscosity {
+            infos.push((
+                format!("{viscosity:.1e} m²/s"),
+                "Eff. Viscosity".to_string(),
+            ));
+        }
+        infos.push((self.probe_count.to_string(), "Probes".to_string()));
+        if let Some(forces) = self.obstacle_forces {
+            infos.extend([
+                (format!("{:.3} N/m", forces.drag), "Drag".to_string()),
+                (format!("{:.3} N/m", forces.lift), "Lift".to_string()),
+                (
+                    format!("{:.2}", forces.drag_coefficient),
+                    "Drag coef.".to_string(),
+                ),
+                (
+                    format!("{:.2}", forces.lift_coefficient),
+                    "Lift coef.".to_string(),
+                ),
+            ]);
+        }
+        if let Some(frequency) = self.shedding_frequency {
+            infos.push((format!("{frequency:.2} Hz"), "Shedding".to_string()));
+        }
+        if let Some(strouhal_number) = self.strouhal_number {
+            infos.push((format!("{strouhal_number:.3}"), "Strouhal".to_string()));
+        }
+        if let Some(probe_export) = &self.probe_export {
+            infos.push((probe_export.clone(), "Export".to_string()));
+        }
         render_left_right_text(&infos, area, buf);
     }
 }
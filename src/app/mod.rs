@@ -1,7 +1,10 @@
+#[allow(clippy::module_inception)]
 mod app;
 mod config;
+mod diagnostics;
 mod info;
 
 pub use app::*;
-pub use config::AppConfig;
+pub use config::{AppConfig, SimResolution, SolverKind};
+pub use diagnostics::DiagnosticsHistory;
 pub use info::AppInfo;
@@ -1,10 +1,17 @@
+use std::fs::File;
+use std::io::BufWriter;
 use std::time::Duration;
 
 use crate::{
-    app::{App, AppState},
-    ui::{editor::editor_area_to_sim_coordinates, render_app},
+    app::{App, AppState, EditorTool},
+    ui::{
+        render_app,
+        view::{HalfBlock, SimView},
+        RenderMode,
+    },
     Result,
 };
+use terminal_fluid_sim::{Emitter, HeatSource, Probe};
 // use color_eyre::eyre::Result;
 use ratatui::{
     buffer::Buffer,
@@ -13,7 +20,6 @@ use ratatui::{
     },
     layout::Rect,
 };
-use terminal_fluid_sim::FluidSim;
 
 /// Handle any events that have occurred since the last time the app was rendered.
 pub fn handle_events(app: &mut App) -> Result<()> {
@@ -37,13 +43,31 @@ pub fn handle_events(app: &mut App) -> Result<()> {
     Ok(())
 }
 
+/// where the probes are exported
+const PROBES_FILE: &str = "probes.csv";
+
+/// how many samples every probe keeps
+const PROBE_HISTORY: usize = 4096;
+
+/// how many tracers an emitter adds per second
+const EMITTER_RATE: f32 = 30.0;
+
 fn handle_key(app: &mut App, code: KeyCode) {
     match code {
         KeyCode::Char('q') | KeyCode::Char('Q') => {
             app.state = AppState::Quit;
         }
+        KeyCode::Char('e') | KeyCode::Char('E') => {
+            if let Some(sim) = app.sim.as_fluid_sim() {
+                // a failed export is shown in the info panel instead of closing the app
+                let result = File::create(PROBES_FILE)
+                    .and_then(|file| sim.write_probes_csv(BufWriter::new(file)));
+                app.info.set_probe_export(PROBES_FILE, result);
+            }
+        }
         KeyCode::Tab => {
-            app.fluid_sim.restart_sim();
+            app.sim.restart_sim();
+            app.diagnostics.clear();
             app.state = match app.state {
                 AppState::Running => AppState::Editing,
                 AppState::Editing => AppState::Running,
@@ -53,24 +77,59 @@ fn handle_key(app: &mut App, code: KeyCode) {
         _ => {}
     }
 
+    if app.state == AppState::Editing {
+        if let KeyCode::Char('t') | KeyCode::Char('T') = code {
+            app.editor_info.tool = app.editor_info.tool.next(app.simulates_heat());
+        }
+    }
+
     if app.state == AppState::Running {
         match code {
+            KeyCode::Char('m') | KeyCode::Char('M') => {
+                app.render_mode = app.render_mode.next();
+                // the averages are only accumulated while they are shown
+                if let Some(sim) = app.sim.as_fluid_sim_mut() {
+                    sim.enable_statistics(app.render_mode != RenderMode::Instant);
+                }
+            }
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                if let Some(sim) = app.sim.as_fluid_sim_mut() {
+                    sim.reset_statistics();
+                }
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                app.show_streamlines = !app.show_streamlines;
+            }
             KeyCode::Up => app.config.up_select(),
             KeyCode::Down => app.config.down_select(),
             KeyCode::Left | KeyCode::Right => {
+                let solver = app.config.get_solver();
                 match code {
                     KeyCode::Left => app.config.reduce_selection(),
                     KeyCode::Right => app.config.increase_selection(),
                     _ => {}
                 }
-                app.fluid_sim.set_config(app.config.get_config());
+                if app.config.get_solver() != solver {
+                    let (width, height) = app.sim.get_size();
+                    let config = app.config.get_config();
+                    app.set_solver(app.config.get_solver().create(width, height, config));
+                } else {
+                    app.sim.set_config(app.config.get_config());
+                }
+                // the heat tools are put away when the new mode or solver has no heat
+                if app.editor_info.tool.is_heat_source() && !app.simulates_heat() {
+                    app.editor_info.tool = EditorTool::Draw;
+                }
+
+                let editor_area = app.editor_info.editor_area;
+                resize_sim(app, editor_area.width, editor_area.height);
             }
             _ => {}
         }
     }
 }
 
-fn handle_resize(app: &mut App, width: u16, height: u16) {
+pub fn handle_resize(app: &mut App, width: u16, height: u16) {
     let previous_state = app.state.clone();
     app.state = AppState::Resizing;
 
@@ -83,27 +142,47 @@ fn handle_resize(app: &mut App, width: u16, height: u16) {
     let mut empty_buffer = Buffer::empty(area);
     let new_sim_area = render_app(app, area, &mut empty_buffer);
 
+    app.editor_info.editor_area = new_sim_area;
     resize_sim(app, new_sim_area.width, new_sim_area.height);
 
     app.state = previous_state;
 }
 
-/// resizes the sim
-/// note: the sim height is double the render height to use half blocks
+/// resizes the sim according to the configured resolution
+/// note: the view height is double the render height to use half blocks
 fn resize_sim(app: &mut App, render_width: u16, render_height: u16) {
-    let (width, height) = (render_width as usize, (render_height * 2) as usize);
-    let (sim_width, sim_height) = app.fluid_sim.get_size();
+    let (width, height) = app
+        .config
+        .get_resolution()
+        .sim_size(render_width as usize, (render_height * 2) as usize);
+    let (sim_width, sim_height) = app.sim.get_size();
 
     if width != sim_width || height != sim_height {
-        app.fluid_sim.resize(width, height);
+        app.sim.resize(width, height);
+        app.diagnostics.clear();
+        // the selected cell is not on the same obstacle anymore
+        app.editor_info.selected_obstacle = None;
     }
 }
 
 fn handle_mouse_event(mouse_event: MouseEvent, app: &mut App) {
+    let sim_area = &app.editor_info.editor_area;
+    let (column, row) = (mouse_event.column, mouse_event.row);
+
+    // the running sim can only get droplets
+    if app.state == AppState::Running {
+        if mouse_event.kind == MouseEventKind::Down(MouseButton::Left)
+            && is_within_bounding_box(column, row, sim_area)
+        {
+            let view = SimView::new(*sim_area, app.sim.get_size());
+            let (x, y) = view.to_sim(column, row, HalfBlock::Lower);
+            app.sim.add_droplet(x, y);
+        }
+        return;
+    }
     if app.state != AppState::Editing {
         return;
     }
-    let sim_area = &app.editor_info.editor_area;
 
     // checking bounds
     if !is_within_bounding_box(mouse_event.column, mouse_event.row, sim_area) {
@@ -114,36 +193,134 @@ fn handle_mouse_event(mouse_event: MouseEvent, app: &mut App) {
     app.editor_info.last_mouse_pos = Some((mouse_event.column, mouse_event.row));
 
     if let MouseEventKind::Down(button) = mouse_event.kind {
-        let (x, y) =
-            editor_area_to_sim_coordinates((mouse_event.column, mouse_event.row), sim_area);
+        let view = SimView::new(*sim_area, app.sim.get_size());
+        let (column, row) = (mouse_event.column, mouse_event.row);
+        let (down_x, down_y) = view.to_sim(column, row, HalfBlock::Lower);
+        let (up_x, up_y) = view.to_sim(column, row, HalfBlock::Upper);
+        let blocks = app.sim.get_block_grid();
+
+        match app.editor_info.tool {
+            EditorTool::Draw => {}
+            EditorTool::Select => {
+                let sim = app.sim.as_ref();
+                match button {
+                    MouseButton::Left if blocks[(down_x, down_y)] => {
+                        app.editor_info.select_obstacle(sim, (down_x, down_y));
+                    }
+                    MouseButton::Left if blocks[(up_x, up_y)] => {
+                        app.editor_info.select_obstacle(sim, (up_x, up_y));
+                    }
+                    MouseButton::Left => {}
+                    _ => app.editor_info.selected_obstacle = None,
+                }
+                return;
+            }
+            EditorTool::Probe => {
+                let Some(sim) = app.sim.as_fluid_sim_mut() else {
+                    return;
+                };
+                match button {
+                    MouseButton::Left => {
+                        sim.add_probe(Probe::new(down_x, down_y, PROBE_HISTORY));
+                    }
+                    MouseButton::Right => {
+                        // remove top probe first
+                        sim.remove_probe(up_x, up_y)
+                            .or_else(|| sim.remove_probe(down_x, down_y));
+                    }
+                    _ => {}
+                }
+                return;
+            }
+            EditorTool::Emitter => {
+                let Some(sim) = app.sim.as_fluid_sim_mut() else {
+                    return;
+                };
+                let cell_size = sim.get_config().cell_size;
+                match button {
+                    MouseButton::Left => {
+                        let down = (
+                            (down_x as f32 + 0.5) * cell_size,
+                            (down_y as f32 + 0.5) * cell_size,
+                        );
+                        sim.get_tracers_mut()
+                            .add_emitter(Emitter::new(down, EMITTER_RATE));
+                    }
+                    MouseButton::Right => {
+                        // remove top emitter first, the emitters are found by the cell they are in
+                        let emitters = sim.get_tracers().emitters();
+                        let index = [(up_x, up_y), (down_x, down_y)]
+                            .into_iter()
+                            .find_map(|cell| {
+                                emitters.iter().position(|emitter| {
+                                    let (x, y) = emitter.position;
+                                    sim.cell_at(x, y) == Some(cell)
+                                })
+                            });
+                        if let Some(index) = index {
+                            sim.get_tracers_mut().remove_emitter(index);
+                        }
+                    }
+                    _ => {}
+                }
+                return;
+            }
+            EditorTool::Heater | EditorTool::Cooler => {
+                let Some(sim) = app.sim.as_fluid_sim_mut() else {
+                    return;
+                };
+                let source = if app.editor_info.tool == EditorTool::Heater {
+                    HeatSource::Heater
+                } else {
+                    HeatSource::Cooler
+                };
+                let sources = sim.get_heat_sources();
+                match button {
+                    MouseButton::Left => {
+                        // set down source first
+                        if sources[(down_x, down_y)] != Some(source) {
+                            sim.set_heat_source(down_x, down_y, source);
+                        } else {
+                            sim.set_heat_source(up_x, up_y, source);
+                        }
+                    }
+                    MouseButton::Right => {
+                        // unset top source first
+                        if sources[(up_x, up_y)] == Some(source) {
+                            sim.unset_heat_source(up_x, up_y);
+                        } else if sources[(down_x, down_y)] == Some(source) {
+                            sim.unset_heat_source(down_x, down_y);
+                        }
+                    }
+                    _ => {}
+                }
+                return;
+            }
+        }
 
         match button {
             MouseButton::Left => {
-                let down_index =
-                    FluidSim::calculate_index_with_height((sim_area.height * 2) as usize, x, y);
-
-                let down_is_block = app.fluid_sim.get_block_grid()[down_index];
+                let down_is_block = blocks[(down_x, down_y)];
                 // set down block first
                 if !down_is_block {
-                    app.fluid_sim.set_block(x, y);
+                    app.sim.set_block(down_x, down_y);
                 } else {
-                    app.fluid_sim.set_block(x, y + 1);
+                    app.sim.set_block(up_x, up_y);
                 }
             }
             MouseButton::Right => {
-                let up_index =
-                    FluidSim::calculate_index_with_height((sim_area.height * 2) as usize, x, y + 1);
-
-                let up_is_block = app.fluid_sim.get_block_grid()[up_index];
+                let up_is_block = blocks[(up_x, up_y)];
                 // unset top block first
                 if up_is_block {
-                    app.fluid_sim.unset_block(x, y + 1);
+                    app.sim.unset_block(up_x, up_y);
                 } else {
-                    app.fluid_sim.unset_block(x, y);
+                    app.sim.unset_block(down_x, down_y);
                 }
             }
             _ => {}
         }
+        // the edited block may join or split the selected obstacle
+        app.editor_info.refresh_obstacle(app.sim.as_ref());
     }
 }
 
@@ -1,3 +1,5 @@
 mod simulator;
 
+pub mod render;
+
 pub use simulator::*;
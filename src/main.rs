@@ -3,18 +3,41 @@ mod handler;
 mod tui;
 mod ui;
 
-use app::App;
+use app::{App, SimResolution};
 // use color_eyre::Result;
 use tui::*;
 
 /// error for the app
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+const USAGE: &str = "usage: terminal-fluid-sim [--scale <factor> | --size <width>x<height>]";
+
 fn main() -> Result<()> {
+    let resolution = parse_resolution(std::env::args().skip(1))?;
+
     install_error_hooks()?;
     let terminal = init_terminal()?;
-    App::default().run(terminal)?;
+    let mut app = App::default();
+    app.config.set_resolution(resolution);
+    app.run(terminal)?;
     restore_terminal()?;
 
     Ok(())
 }
+
+/// reads the simulation resolution from the command line arguments
+fn parse_resolution(mut args: impl Iterator<Item = String>) -> Result<SimResolution> {
+    let resolution = match (args.next().as_deref(), args.next()) {
+        (None, _) => SimResolution::default(),
+        (Some("--scale"), Some(scale)) => SimResolution::Scale(scale.parse()?),
+        (Some("--size"), Some(size)) => {
+            let (width, height) = size.split_once('x').ok_or(USAGE)?;
+            SimResolution::Fixed {
+                width: width.parse()?,
+                height: height.parse()?,
+            }
+        }
+        _ => return Err(USAGE.into()),
+    };
+    Ok(resolution)
+}
@@ -0,0 +1,10 @@
+//! draws the solvers into ratatui buffers, two cells of the sim in every terminal cell
+
+mod sim_renderer;
+mod theme;
+pub mod view;
+
+pub use sim_renderer::{
+    render_heat_sources, render_probes, render_sim, render_streamlines, render_tracers, RenderMode,
+};
+pub use theme::{Theme, THEME};
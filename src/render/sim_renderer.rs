@@ -0,0 +1,394 @@
+use ratatui::prelude::*;
+use ratatui::{
+    buffer::Buffer,
+    style::Color,
+    symbols::Marker,
+    widgets::{
+        canvas::{Canvas, Line},
+        Widget,
+    },
+};
+
+use crate::{FluidSim, HeatSource, Shading, Solver, StreamlineSettings};
+
+use super::{
+    theme::THEME,
+    view::{HalfBlock, SimView},
+};
+
+/// which fields are shown by [`render_sim`]
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    /// the fields of the last step
+    #[default]
+    Instant,
+
+    /// the mean of the fields since the statistics were reset
+    Mean,
+
+    /// the root mean square of the pressure fluctuations since the statistics were reset
+    Rms,
+}
+
+impl RenderMode {
+    pub fn next(self) -> Self {
+        match self {
+            RenderMode::Instant => RenderMode::Mean,
+            RenderMode::Mean => RenderMode::Rms,
+            RenderMode::Rms => RenderMode::Instant,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderMode::Instant => "Instant",
+            RenderMode::Mean => "Mean",
+            RenderMode::Rms => "RMS",
+        }
+    }
+}
+
+pub fn render_sim<L: crate::Layout>(
+    sim: &dyn Solver<f32, L>,
+    mode: RenderMode,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let mut max_pressure = f32::MIN;
+    let mut min_pressure = f32::MAX;
+
+    // the averaged fields need the statistics of the sim, the last step is shown without them
+    let rms_pressure;
+    let fluid_sim = sim.as_fluid_sim();
+    let statistics = fluid_sim.and_then(FluidSim::get_statistics);
+    let (pressure_grid, smoke_grid) = match (mode, statistics) {
+        (RenderMode::Mean, Some(statistics)) => {
+            (statistics.pressure.mean(), Some(statistics.smoke.mean()))
+        }
+        (RenderMode::Rms, Some(statistics)) => {
+            rms_pressure = statistics.pressure.rms();
+            (&rms_pressure, None)
+        }
+        _ => (sim.get_pressure_grid(), Some(sim.get_smoke_grid())),
+    };
+    let block_grid = sim.get_block_grid();
+    let temperature_grid = fluid_sim.and_then(FluidSim::get_temperature_grid);
+    let phase_grid = fluid_sim.and_then(FluidSim::get_phase_grid);
+
+    pressure_grid.iter().for_each(|&pressure_value| {
+        if pressure_value < min_pressure {
+            min_pressure = pressure_value;
+        } else if pressure_value > max_pressure {
+            max_pressure = pressure_value
+        }
+    });
+
+    // the simulation is sampled onto the half blocks of the area
+    // this makes it so we can have double the "pixels" vertically
+    // by using fg and bg colors
+    let view = SimView::new(area, sim.get_size());
+    let shading = sim.shading();
+    let cell_color = |position: (usize, usize)| {
+        if block_grid[position] {
+            return THEME.sim_blocks;
+        }
+        let smoke = smoke_grid.map_or(0.0, |smoke_grid| smoke_grid[position]);
+        let pressure = pressure_grid[position];
+        match shading {
+            Shading::Smoke => get_color(smoke, pressure, min_pressure, max_pressure),
+            // the liquid keeps the empty part of the cells in the smoke field
+            Shading::Liquid => get_liquid_color(smoke, pressure, min_pressure, max_pressure),
+            Shading::Fire => get_fire_color(
+                smoke,
+                temperature_grid.map_or(0.0, |temperature_grid| temperature_grid[position]),
+            ),
+            Shading::Phases => get_phase_color(
+                phase_grid.map_or(0.0, |phase_grid| phase_grid[position]),
+                pressure,
+                min_pressure,
+                max_pressure,
+            ),
+            Shading::Height => {
+                let (x, y) = position;
+                // the neighbors outside of the grid are as high as the cell
+                let at = |x: Option<usize>, y: Option<usize>| {
+                    x.zip(y)
+                        .and_then(|(x, y)| pressure_grid.get(x, y))
+                        .copied()
+                        .unwrap_or(pressure)
+                };
+                let slope = (
+                    at(x.checked_add(1), Some(y)) - at(x.checked_sub(1), Some(y)),
+                    at(Some(x), y.checked_add(1)) - at(Some(x), y.checked_sub(1)),
+                );
+                get_height_color(pressure, slope, min_pressure, max_pressure)
+            }
+        }
+    };
+
+    for x_pos in area.left()..area.right() {
+        for y_pos in area.top()..area.bottom() {
+            // the top block is the background and the foreground is the bottom
+            let top_color = cell_color(view.to_sim(x_pos, y_pos, HalfBlock::Upper));
+            let bottom_color = cell_color(view.to_sim(x_pos, y_pos, HalfBlock::Lower));
+
+            buf.get_mut(x_pos, y_pos)
+                .set_char('▄')
+                .set_bg(top_color)
+                .set_fg(bottom_color);
+        }
+    }
+
+    if let Some(sim) = fluid_sim {
+        if sim.simulates_heat() {
+            render_heat_sources(sim, &view, buf);
+        }
+        render_tracers(sim, &view, buf);
+        render_probes(sim, &view, buf);
+    }
+}
+
+/// colors the half blocks showing the heaters and coolers of the sim
+pub fn render_heat_sources<L: crate::Layout>(
+    sim: &FluidSim<f32, L>,
+    view: &SimView,
+    buf: &mut Buffer,
+) {
+    let sources = sim.get_heat_sources();
+    for (x, y) in sources.positions() {
+        let color = match sources[(x, y)] {
+            Some(HeatSource::Heater) => THEME.heaters,
+            Some(HeatSource::Cooler) => THEME.coolers,
+            None => continue,
+        };
+        paint_half_block(view.to_view(x, y), color, buf);
+    }
+}
+
+/// colors the half blocks showing the probes of the sim
+pub fn render_probes<L: crate::Layout>(sim: &FluidSim<f32, L>, view: &SimView, buf: &mut Buffer) {
+    for probe in sim.get_probes() {
+        let (x, y) = probe.position();
+        paint_half_block(view.to_view(x, y), THEME.probes, buf);
+    }
+}
+
+/// colors the half blocks showing the tracers and their emitters
+pub fn render_tracers<L: crate::Layout>(sim: &FluidSim<f32, L>, view: &SimView, buf: &mut Buffer) {
+    let tracers = sim.get_tracers();
+    let cell_size = sim.get_config().cell_size;
+    let to_view =
+        |(x, y): (f32, f32)| view.to_view((x / cell_size) as usize, (y / cell_size) as usize);
+
+    for tracer in tracers.tracers() {
+        paint_half_block(to_view(tracer.position), THEME.tracers, buf);
+    }
+    for emitter in tracers.emitters() {
+        paint_half_block(to_view(emitter.position), THEME.emitters, buf);
+    }
+}
+
+/// draws the streamlines of the sim with braille dots over what is already in the area
+pub fn render_streamlines(sim: &FluidSim, area: Rect, buf: &mut Buffer) {
+    let streamlines = sim.streamlines(&StreamlineSettings::default());
+    let (domain_width, domain_height) = sim.get_domain_size();
+
+    let canvas = Canvas::default()
+        .marker(Marker::Braille)
+        .x_bounds([0.0, domain_width as f64])
+        .y_bounds([0.0, domain_height as f64])
+        .paint(|ctx| {
+            for streamline in &streamlines {
+                for (&(x1, y1), &(x2, y2)) in streamline.points.iter().zip(&streamline.points[1..])
+                {
+                    ctx.draw(&Line::new(
+                        x1 as f64,
+                        y1 as f64,
+                        x2 as f64,
+                        y2 as f64,
+                        THEME.streamlines,
+                    ));
+                }
+            }
+        });
+
+    // the canvas clears the background of its area so it is drawn on its own buffer
+    // and only the dots are copied, keeping the colors of the fields behind them
+    let mut lines_buf = Buffer::empty(area);
+    canvas.render(area, &mut lines_buf);
+    for column in area.left()..area.right() {
+        for row in area.top()..area.bottom() {
+            let line_cell = lines_buf.get(column, row);
+            if line_cell.symbol() != " " {
+                buf.get_mut(column, row)
+                    .set_symbol(line_cell.symbol())
+                    .set_fg(line_cell.fg);
+            }
+        }
+    }
+}
+
+/// colors one half of a terminal cell
+fn paint_half_block((column, row, half): (u16, u16, HalfBlock), color: Color, buf: &mut Buffer) {
+    let cell = buf.get_mut(column, row);
+
+    // a lower half block lets each half have its own color
+    if cell.symbol() != "▄" {
+        let background = cell.bg;
+        cell.set_char('▄').set_fg(background);
+    }
+    match half {
+        HalfBlock::Upper => cell.set_bg(color),
+        HalfBlock::Lower => cell.set_fg(color),
+    };
+}
+
+fn get_linear_gradient(value: f32, min: f32, max: f32) -> (u8, u8, u8) {
+    let difference = max - min;
+
+    let normalized = if difference == 0.0 {
+        0.5
+    } else {
+        (value - min) / difference
+    };
+
+    let color_type = (normalized * 4.0).floor();
+
+    let color_value = (normalized - color_type * 0.25) / 0.25;
+
+    let (r, g, b) = match color_type as u8 {
+        0 => (0.0, color_value, 1.0),
+        1 => (0.0, 1.0, 1.0 - color_value),
+        2 => (color_value, 1.0, 0.0),
+        3 => (1.0, 1.0 - color_value, 0.0),
+        _ => (1.0, 0.0, 0.0),
+    };
+
+    let (r, g, b) = ((255.0 * r) as u8, (255.0 * g) as u8, (255.0 * b) as u8);
+
+    (r, g, b)
+}
+
+fn get_color(smoke: f32, pressure: f32, min_pressure: f32, max_pressure: f32) -> Color {
+    let (r, g, b) = get_linear_gradient(pressure, min_pressure, max_pressure);
+    let smoke_reducer = (255.0 * smoke) as u8;
+
+    Color::Rgb(
+        r.saturating_sub(smoke_reducer),
+        g.saturating_sub(smoke_reducer),
+        b.saturating_sub(smoke_reducer),
+    )
+}
+
+/// shades the liquid from light blue at the surface to deep blue where the pressure is the highest
+fn get_liquid_color(empty: f32, pressure: f32, min_pressure: f32, max_pressure: f32) -> Color {
+    const SURFACE: (f32, f32, f32) = (120.0, 200.0, 255.0);
+    const DEPTH: (f32, f32, f32) = (0.0, 40.0, 160.0);
+
+    let difference = max_pressure - min_pressure;
+    let depth = if difference == 0.0 {
+        0.0
+    } else {
+        ((pressure - min_pressure) / difference).clamp(0.0, 1.0)
+    };
+    let fullness = (1.0 - empty).clamp(0.0, 1.0);
+    let channel = |surface: f32, deep: f32| ((surface + (deep - surface) * depth) * fullness) as u8;
+
+    Color::Rgb(
+        channel(SURFACE.0, DEPTH.0),
+        channel(SURFACE.1, DEPTH.1),
+        channel(SURFACE.2, DEPTH.2),
+    )
+}
+
+/// shades a water surface from deep blue in the troughs to light blue on the crests,
+/// the slopes facing the top left are lit and the others are in the shadow
+fn get_height_color(
+    height: f32,
+    (slope_x, slope_y): (f32, f32),
+    min_height: f32,
+    max_height: f32,
+) -> Color {
+    const TROUGH: (f32, f32, f32) = (0.0, 60.0, 150.0);
+    const CREST: (f32, f32, f32) = (90.0, 180.0, 255.0);
+    const LIGHT_STRENGTH: f32 = 2.0;
+
+    let difference = max_height - min_height;
+    let (level, light) = if difference == 0.0 {
+        (0.5, 1.0)
+    } else {
+        (
+            ((height - min_height) / difference).clamp(0.0, 1.0),
+            (1.0 + LIGHT_STRENGTH * (slope_x - slope_y) / difference).clamp(0.4, 1.4),
+        )
+    };
+    let channel = |trough: f32, crest: f32| {
+        ((trough + (crest - trough) * level) * light).clamp(0.0, 255.0) as u8
+    };
+
+    Color::Rgb(
+        channel(TROUGH.0, CREST.0),
+        channel(TROUGH.1, CREST.1),
+        channel(TROUGH.2, CREST.2),
+    )
+}
+
+/// shades the smoke in gray and the flames like a black body, from dark red through orange
+/// and yellow to white as they get hotter
+fn get_fire_color(smoke: f32, temperature: f32) -> Color {
+    /// the colors of the glow at some temperatures above the ambient one in K
+    const GLOW: [(f32, (f32, f32, f32)); 6] = [
+        (0.0, (0.0, 0.0, 0.0)),
+        (300.0, (90.0, 0.0, 0.0)),
+        (600.0, (200.0, 30.0, 0.0)),
+        (900.0, (255.0, 120.0, 0.0)),
+        (1200.0, (255.0, 210.0, 60.0)),
+        (1600.0, (255.0, 255.0, 210.0)),
+    ];
+    const SMOKE: f32 = 110.0;
+
+    let temperature = temperature.clamp(0.0, GLOW[GLOW.len() - 1].0);
+    let upper = GLOW
+        .iter()
+        .position(|&(stop, _)| stop >= temperature)
+        .unwrap_or(GLOW.len() - 1)
+        .max(1);
+    let (low, (low_r, low_g, low_b)) = GLOW[upper - 1];
+    let (high, (high_r, high_g, high_b)) = GLOW[upper];
+    let ratio = (temperature - low) / (high - low);
+    let glow = |low: f32, high: f32| low + (high - low) * ratio;
+
+    // the glow lights the smoke up, the cold smoke stays gray
+    let gray = SMOKE * (1.0 - smoke).clamp(0.0, 1.0);
+    let channel = |glow: f32| glow.max(gray).clamp(0.0, 255.0) as u8;
+
+    Color::Rgb(
+        channel(glow(low_r, high_r)),
+        channel(glow(low_g, high_g)),
+        channel(glow(low_b, high_b)),
+    )
+}
+
+/// shades the first fluid in blue and the second one in orange, mixed where they are both
+/// in a cell and darker where the pressure is higher
+fn get_phase_color(phase: f32, pressure: f32, min_pressure: f32, max_pressure: f32) -> Color {
+    const FIRST: (f32, f32, f32) = (40.0, 110.0, 220.0);
+    const SECOND: (f32, f32, f32) = (240.0, 140.0, 40.0);
+    const MAX_SHADOW: f32 = 0.4;
+
+    let difference = max_pressure - min_pressure;
+    let shadow = if difference == 0.0 {
+        0.0
+    } else {
+        MAX_SHADOW * ((pressure - min_pressure) / difference).clamp(0.0, 1.0)
+    };
+    let phase = phase.clamp(0.0, 1.0);
+    let channel =
+        |first: f32, second: f32| ((first + (second - first) * phase) * (1.0 - shadow)) as u8;
+
+    Color::Rgb(
+        channel(FIRST.0, SECOND.0),
+        channel(FIRST.1, SECOND.1),
+        channel(FIRST.2, SECOND.2),
+    )
+}
@@ -7,6 +7,13 @@ pub struct Theme {
     pub title: Style,
     pub controls: Style,
     pub sim_blocks: Color,
+    pub selected_blocks: Color,
+    pub probes: Color,
+    pub emitters: Color,
+    pub heaters: Color,
+    pub coolers: Color,
+    pub tracers: Color,
+    pub streamlines: Color,
     pub tab_text: Style,
     pub highlight_config: Style,
 }
@@ -18,6 +25,13 @@ pub const THEME: Theme = Theme {
     title: Style::new().add_modifier(Modifier::BOLD),
     controls: Style::new().bg(Color::White).fg(Color::Black),
     sim_blocks: Color::White,
+    selected_blocks: Color::Yellow,
+    probes: Color::Magenta,
+    emitters: Color::LightGreen,
+    heaters: Color::LightRed,
+    coolers: Color::LightBlue,
+    tracers: Color::LightYellow,
+    streamlines: Color::Cyan,
     tab_text: Style::new().fg(Color::White),
     highlight_config: Style::new().fg(Color::Black).bg(Color::White),
 };
@@ -0,0 +1,70 @@
+use ratatui::layout::Rect;
+
+/// which half of a terminal cell is being looked at
+#[derive(Clone, Copy, PartialEq)]
+pub enum HalfBlock {
+    Upper,
+    Lower,
+}
+
+/// maps the half block "pixels" of a terminal area onto the cells of the simulation
+///
+/// the simulation does not need to have the same size as the area it is drawn in,
+/// every half block picks the nearest simulation cell which up samples a coarse
+/// simulation and down samples a fine one
+pub struct SimView {
+    area: Rect,
+    sim_width: usize,
+    sim_height: usize,
+}
+
+impl SimView {
+    pub fn new(area: Rect, (sim_width, sim_height): (usize, usize)) -> Self {
+        Self {
+            area,
+            sim_width,
+            sim_height,
+        }
+    }
+
+    /// returns the simulation cell shown in a half of the terminal cell at (column, row)
+    ///
+    /// the rendering origin is top left while the simulation origin is bottom left
+    pub fn to_sim(&self, column: u16, row: u16, half: HalfBlock) -> (usize, usize) {
+        let view_width = self.area.width.max(1) as usize;
+        let view_height = (self.area.height.max(1) * 2) as usize;
+
+        let x = column.saturating_sub(self.area.x) as usize;
+        // half blocks counted from the bottom of the area
+        let rows_from_bottom = (self.area.bottom().saturating_sub(row + 1)) as usize;
+        let y = match half {
+            HalfBlock::Lower => rows_from_bottom * 2,
+            HalfBlock::Upper => rows_from_bottom * 2 + 1,
+        };
+
+        (
+            (x * self.sim_width / view_width).min(self.sim_width - 1),
+            (y * self.sim_height / view_height).min(self.sim_height - 1),
+        )
+    }
+
+    /// returns the terminal cell and the half of it that shows the simulation cell (x, y),
+    /// the inverse of [`SimView::to_sim`]
+    pub fn to_view(&self, x: usize, y: usize) -> (u16, u16, HalfBlock) {
+        let view_width = self.area.width.max(1) as usize;
+        let view_height = (self.area.height.max(1) * 2) as usize;
+
+        // the half block closest to the center of the simulation cell
+        let column = ((2 * x + 1) * view_width / (2 * self.sim_width)).min(view_width - 1);
+        let half_from_bottom =
+            ((2 * y + 1) * view_height / (2 * self.sim_height)).min(view_height - 1);
+
+        let half = if half_from_bottom.is_multiple_of(2) {
+            HalfBlock::Lower
+        } else {
+            HalfBlock::Upper
+        };
+        let row = self.area.bottom() - 1 - (half_from_bottom / 2) as u16;
+        (self.area.x + column as u16, row, half)
+    }
+}
@@ -1,25 +1,171 @@
+use crate::Scalar;
+
+/// what fills the domain of the simulation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SimMode {
+    /// a domain full of fluid with wind blowing smoke from the left
+    #[default]
+    Smoke,
+
+    /// a closed tank partly filled with a liquid carried by particles,
+    /// see [`Liquid`](crate::Liquid)
+    Liquid,
+
+    /// the wind blows fuel from the left instead of smoke, it burns into heat and smoke
+    /// once it is hotter than the ignition temperature and the hot gas rises
+    Fire,
+
+    /// a closed tank without wind with a second fluid above the first one,
+    /// the heavier one sinks through the other when there is gravity (Rayleigh–Taylor)
+    TwoPhase,
+}
+
 #[derive(Clone)]
 pub struct SimConfig {
-    /// gravity of the simulation, set to 0 for no gravity
-    pub gravity: f32,
+    /// gravity of the simulation in m/s² along x and y, y points up,
+    /// set to (0, 0) for no gravity and tilt it to tilt the tank
+    pub gravity: (f32, f32),
+
+    /// angular speed in rad/s of the frame the domain is seen from, counterclockwise around
+    /// the center of the domain, set to 0 for a frame at rest
+    ///
+    /// the fluid at rest in a rotating frame feels the Coriolis and centrifugal forces
+    pub rotation_rate: f32,
 
-    /// wind speed, must be above 0
+    /// wind speed in m/s, must be above 0
     pub wind_speed: f32,
 
     /// some height percentage of the screen [0,1] inclusive
     pub smoke_size: f32,
 
-    /// density of the sim
+    /// density of the fluid in kg/m³
     pub density: f32,
+
+    /// the size of a single cell in meters
+    pub cell_size: f32,
+
+    /// the width of the domain in meters, `None` to let it grow with the grid
+    ///
+    /// when set the solvers replace the cell size by this width over the number of columns,
+    /// so resizing the grid refines the cells instead of growing the domain. the cells stay
+    /// square so the height of the domain follows the number of rows
+    pub domain_width: Option<f32>,
+
+    /// kinematic viscosity of the fluid in m²/s, set to 0 for an inviscid fluid
+    pub viscosity: f32,
+
+    /// what fills the domain, changing it restarts the simulation
+    pub mode: SimMode,
+
+    /// the part of the velocity of the liquid particles updated with the change of the grid
+    /// velocities (FLIP) instead of being replaced by them (PIC), in [0, 1]
+    ///
+    /// PIC is stable but damps the motion while FLIP keeps it lively but noisy
+    pub flip_ratio: f32,
+
+    /// the temperature above the ambient one in K where the fuel starts burning
+    pub ignition_temperature: f32,
+
+    /// the part of the fuel of a burning cell consumed every second in 1/s
+    pub burn_rate: f32,
+
+    /// the upward acceleration of the gas in m/s² for every K above the ambient temperature
+    pub buoyancy: f32,
+
+    /// the part of the temperature above the ambient one lost every second in 1/s
+    pub cooling_rate: f32,
+
+    /// density of the second fluid of the two phase mode in kg/m³,
+    /// the first one has the density of the config
+    pub second_density: f32,
+
+    /// surface tension of the liquid mode in N/m, set to 0 to let the liquid break freely
+    pub surface_tension: f32,
+
+    /// the temperature above the ambient one in K the heaters hold,
+    /// the coolers hold it below the ambient one
+    pub heater_temperature: f32,
+
+    /// the temperature above the ambient one in K the blocks hold and conduct into the fluid
+    /// around them in the smoke and fire modes, `None` for blocks that do not conduct heat
+    pub wall_temperature: Option<f32>,
 }
 
+/// the gravity at the surface of the earth in m/s²
+pub const STANDARD_GRAVITY: f32 = 9.81;
+
 impl Default for SimConfig {
     fn default() -> Self {
         Self {
-            gravity: 0.0,
-            wind_speed: 50.0,
+            gravity: (0.0, 0.0),
+            rotation_rate: 0.0,
+            wind_speed: 0.5,
             smoke_size: 0.25,
             density: 1000.0,
+            cell_size: 0.01,
+            domain_width: None,
+            viscosity: 1.0e-6,
+            mode: SimMode::Smoke,
+            flip_ratio: 0.9,
+            ignition_temperature: 400.0,
+            burn_rate: 3.0,
+            buoyancy: 0.003,
+            cooling_rate: 1.5,
+            second_density: 1500.0,
+            surface_tension: 0.072,
+            heater_temperature: 500.0,
+            wall_temperature: None,
         }
     }
 }
+
+impl SimConfig {
+    /// the gravity in m/s² for the solvers of water that need one to move,
+    /// the standard gravity pointing down when the setting is 0
+    #[inline]
+    pub(crate) fn water_gravity(&self) -> (f32, f32) {
+        if self.gravity == (0.0, 0.0) {
+            (0.0, -STANDARD_GRAVITY)
+        } else {
+            self.gravity
+        }
+    }
+
+    /// the acceleration in m/s² of the fluid moving at `velocity` in the rotating frame,
+    /// `offset` is its position from the center of the rotation in meters
+    ///
+    /// the Coriolis force turns the velocity clockwise for a counterclockwise rotation
+    /// and the centrifugal force pushes away from the center
+    #[inline]
+    pub(crate) fn frame_acceleration<T: Scalar>(&self, offset: (T, T), velocity: (T, T)) -> (T, T) {
+        let rate = T::from_f32(self.rotation_rate);
+        let (coriolis, centrifugal) = (T::from_f32(2.0) * rate, rate * rate);
+        (
+            centrifugal * offset.0 + coriolis * velocity.1,
+            centrifugal * offset.1 - coriolis * velocity.0,
+        )
+    }
+
+    /// the largest acceleration of gravity and of the centrifugal force in m/s² on a domain
+    /// of the given size in meters, the Coriolis force only turns the velocity
+    pub(crate) fn max_body_acceleration(&self, (width, height): (f32, f32)) -> f32 {
+        let radius = 0.5 * width.hypot(height);
+        self.gravity.0.hypot(self.gravity.1) + self.rotation_rate * self.rotation_rate * radius
+    }
+
+    /// makes `width` cells span the domain width when there is one
+    pub(crate) fn fit_domain(&mut self, width: usize) {
+        if let Some(domain_width) = self.domain_width {
+            self.cell_size = domain_width / width as f32;
+        }
+    }
+
+    /// returns the width and height of the domain in meters for a grid of the given size
+    #[inline]
+    pub fn domain_size(&self, width: usize, height: usize) -> (f32, f32) {
+        (
+            width as f32 * self.cell_size,
+            height as f32 * self.cell_size,
+        )
+    }
+}
@@ -0,0 +1,131 @@
+use rayon::prelude::*;
+
+use super::liquid::stencil;
+use crate::{FluidSim, Grid, Layout, Scalar, SimConfig};
+
+/// totals and extremes of the fields after a step, to check that the solver
+/// does not lose smoke or add energy
+///
+/// the simulation is 2d so the totals are per meter of depth
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StepDiagnostics<T: Scalar = f32> {
+    /// the amount of smoke in the fluid cells in m², a cell full of smoke counts for its area
+    pub smoke_mass: T,
+
+    /// the kinetic energy of the fluid in J/m
+    pub kinetic_energy: T,
+
+    /// half of the integral of the squared vorticity in m²/s²
+    pub enstrophy: T,
+
+    /// the largest absolute divergence of a cell in 1/s, should stay close to 0
+    pub max_divergence: T,
+
+    /// the largest speed at the center of a cell in m/s
+    pub max_speed: T,
+}
+
+impl<T: Scalar> StepDiagnostics<T> {
+    pub(crate) fn combine(self, other: Self) -> Self {
+        Self {
+            smoke_mass: self.smoke_mass + other.smoke_mass,
+            kinetic_energy: self.kinetic_energy + other.kinetic_energy,
+            enstrophy: self.enstrophy + other.enstrophy,
+            max_divergence: self.max_divergence.max(other.max_divergence),
+            max_speed: self.max_speed.max(other.max_speed),
+        }
+    }
+}
+
+impl<T: Scalar, L: Layout> FluidSim<T, L> {
+    /// computes the diagnostics of the current fields over the fluid cells inside the border
+    pub fn diagnostics(&self) -> StepDiagnostics<T> {
+        let (width, height) = self.get_size();
+        let blocks = self.get_block_grid();
+        let smoke = self.get_smoke_grid();
+        let config = self.get_config();
+        let half = T::from_f32(0.5);
+        let cell_area = T::from_f32(config.cell_size * config.cell_size);
+        let density = T::from_f32(config.density);
+
+        (0..width * height)
+            .into_par_iter()
+            .map(|index| {
+                let (x, y) = blocks.position(index);
+                let is_border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+                if blocks[(x, y)] || is_border {
+                    return StepDiagnostics::default();
+                }
+                let (u, v) = self.cell_velocity(x, y);
+                let squared_speed = u * u + v * v;
+                let vorticity = self.vorticity_at(x, y);
+                StepDiagnostics {
+                    smoke_mass: (T::ONE - smoke[(x, y)]) * cell_area,
+                    kinetic_energy: half * density * squared_speed * cell_area,
+                    enstrophy: half * vorticity * vorticity * cell_area,
+                    max_divergence: self.divergence_at(x, y).abs(),
+                    max_speed: squared_speed.sqrt(),
+                }
+            })
+            .reduce(StepDiagnostics::default, StepDiagnostics::combine)
+    }
+}
+
+/// computes the diagnostics of a solver that stores the velocity at the center of its cells,
+/// `fluid(x, y)` returns the smoke in a cell, relative to a cell full of it, and the height
+/// of its fluid column the kinetic energy is weighted with, `None` outside of the fluid
+///
+/// the divergence and the vorticity are centered differences, left out on the border of the grid
+pub(crate) fn cell_diagnostics<T: Scalar, L: Layout>(
+    velocities: &Grid<(T, T), L>,
+    config: &SimConfig,
+    fluid: impl Fn(usize, usize) -> Option<(T, T)> + Sync,
+) -> StepDiagnostics<T> {
+    let h = T::from_f32(config.cell_size);
+    let cell_area = h * h;
+    let half = T::from_f32(0.5);
+    let density = T::from_f32(config.density);
+    let (width, height) = velocities.size();
+
+    (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = velocities.position(index);
+            let Some((smoke, column)) = fluid(x, y) else {
+                return StepDiagnostics::default();
+            };
+            let (u, v) = velocities[(x, y)];
+            let squared_speed = u * u + v * v;
+            let mut diagnostics = StepDiagnostics {
+                smoke_mass: smoke * cell_area,
+                kinetic_energy: half * density * column * squared_speed * cell_area,
+                max_speed: squared_speed.sqrt(),
+                ..StepDiagnostics::default()
+            };
+            if x > 0 && y > 0 && x + 1 < width && y + 1 < height {
+                let (left, right) = (velocities[(x - 1, y)], velocities[(x + 1, y)]);
+                let (bottom, top) = (velocities[(x, y - 1)], velocities[(x, y + 1)]);
+                let divergence = ((right.0 - left.0) + (top.1 - bottom.1)) * half / h;
+                let vorticity = ((right.1 - left.1) - (top.0 - bottom.0)) * half / h;
+                diagnostics.enstrophy = half * vorticity * vorticity * cell_area;
+                diagnostics.max_divergence = divergence.abs();
+            }
+            diagnostics
+        })
+        .reduce(StepDiagnostics::default, StepDiagnostics::combine)
+}
+
+/// interpolates a grid of the velocities at the center of the cells at a position in meters
+pub(crate) fn cell_velocity_at<T: Scalar, L: Layout>(
+    velocities: &Grid<(T, T), L>,
+    (x, y): (T, T),
+    h: T,
+) -> (T, T) {
+    let half = T::from_f32(0.5) * h;
+    stencil(velocities, (x, y), (half, half), h)
+        .into_iter()
+        .fold((T::ZERO, T::ZERO), |(u, v), (cell, weight)| {
+            let (cell_u, cell_v) = velocities[cell];
+            (u + weight * cell_u, v + weight * cell_v)
+        })
+}
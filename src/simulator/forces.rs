@@ -0,0 +1,334 @@
+use rayon::prelude::*;
+
+use crate::{FluidSim, Grid, Layout, Scalar, SimConfig};
+
+/// the force of the fluid on an obstacle
+///
+/// the simulation is 2d so the forces are per meter of depth
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ObstacleForces {
+    /// the force along the wind in N/m
+    pub drag: f32,
+
+    /// the force across the wind in N/m, positive upwards
+    pub lift: f32,
+
+    /// the drag divided by the dynamic pressure and the frontal height of the obstacle,
+    /// 0 without wind
+    pub drag_coefficient: f32,
+
+    /// the lift divided by the dynamic pressure and the frontal height of the obstacle,
+    /// 0 without wind
+    pub lift_coefficient: f32,
+}
+
+/// the offsets to the cells sharing a face with a cell
+const FACES: [(isize, isize); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+impl<T: Scalar, L: Layout> FluidSim<T, L> {
+    /// returns every block connected to the block at `(x, y)` by a face,
+    /// empty if `(x, y)` is not a block
+    pub fn obstacle_at(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let blocks = self.get_block_grid();
+        if blocks.get(x, y) != Some(&true) {
+            return Vec::new();
+        }
+
+        let (width, height) = blocks.size();
+        let mut visited = vec![false; width * height];
+        visited[blocks.index(x, y)] = true;
+        let mut to_visit = vec![(x, y)];
+        let mut obstacle = Vec::new();
+
+        while let Some((x, y)) = to_visit.pop() {
+            obstacle.push((x, y));
+            for (neighbor_x, neighbor_y) in Self::face_neighbors(x, y) {
+                if blocks.get(neighbor_x, neighbor_y) != Some(&true) {
+                    continue;
+                }
+                let index = blocks.index(neighbor_x, neighbor_y);
+                if !visited[index] {
+                    visited[index] = true;
+                    to_visit.push((neighbor_x, neighbor_y));
+                }
+            }
+        }
+        obstacle
+    }
+
+    /// integrates the pressure and the viscous stress over the faces between
+    /// the blocks of an obstacle and the fluid
+    ///
+    /// the fluid is at rest on the walls so the shear stress of a face is estimated
+    /// from the velocity at the center of the fluid cell, half a cell away
+    pub fn obstacle_forces(&self, obstacle: &[(usize, usize)]) -> ObstacleForces {
+        let config = self.get_config();
+        let blocks = self.get_block_grid();
+        let pressure = self.get_pressure_grid();
+        let h = T::from_f32(config.cell_size);
+        let dynamic_viscosity = T::from_f32(config.density * config.viscosity);
+        // the shear over a face is the velocity gradient over half a cell times the face length
+        let shear_factor = dynamic_viscosity * T::from_f32(2.0);
+
+        let (mut force_x, mut force_y) = (T::ZERO, T::ZERO);
+        for &(x, y) in obstacle {
+            for ((neighbor_x, neighbor_y), (dx, dy)) in Self::face_neighbors(x, y).zip(FACES) {
+                if blocks.get(neighbor_x, neighbor_y) != Some(&false) {
+                    continue;
+                }
+                // the pressure pushes the face into the obstacle
+                let face_force = pressure[(neighbor_x, neighbor_y)] * h;
+                force_x -= T::from_f32(dx as f32) * face_force;
+                force_y -= T::from_f32(dy as f32) * face_force;
+
+                // the shear drags the face along the flow
+                let (u, v) = self.cell_velocity(neighbor_x, neighbor_y);
+                if dx == 0 {
+                    force_x += shear_factor * u;
+                } else {
+                    force_y += shear_factor * v;
+                }
+            }
+        }
+
+        let (drag, lift) = (force_x.to_f32(), force_y.to_f32());
+        let frontal_height = Self::frontal_height(obstacle, blocks.height()) * config.cell_size;
+        let reference = 0.5 * config.density * config.wind_speed.powi(2) * frontal_height;
+        let coefficient = |force: f32| {
+            if reference > 0.0 {
+                force / reference
+            } else {
+                0.0
+            }
+        };
+
+        ObstacleForces {
+            drag,
+            lift,
+            drag_coefficient: coefficient(drag),
+            lift_coefficient: coefficient(lift),
+        }
+    }
+
+    /// returns the Strouhal number of a vortex shedding frequency in Hz behind an obstacle,
+    /// using the frontal height of the obstacle and the wind speed, 0 without wind
+    pub fn strouhal_number(&self, frequency: f32, obstacle: &[(usize, usize)]) -> f32 {
+        let config = self.get_config();
+        let frontal_height =
+            Self::frontal_height(obstacle, self.get_block_grid().height()) * config.cell_size;
+        if config.wind_speed <= 0.0 {
+            return 0.0;
+        }
+        frequency * frontal_height / config.wind_speed
+    }
+
+    /// the cells sharing a face with `(x, y)` in the order of [`FACES`],
+    /// the ones below 0 are skipped by wrapping them outside of the grid
+    fn face_neighbors(x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        FACES
+            .into_iter()
+            .map(move |(dx, dy)| (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy)))
+    }
+
+    /// the number of rows the obstacle covers
+    fn frontal_height(obstacle: &[(usize, usize)], height: usize) -> f32 {
+        let mut rows = vec![false; height];
+        obstacle.iter().for_each(|&(_, y)| rows[y] = true);
+        rows.into_iter().filter(|&is_covered| is_covered).count() as f32
+    }
+}
+
+/// adds the Coriolis and centrifugal forces of the rotating frame around the center
+/// of the domain to the faces of a staggered grid where `is_open(x, y, step)` is true,
+/// `step` going from the cell before the face to the one after it.
+/// the velocity across a face is the mean of the four faces around it, so the open faces
+/// must not touch the border they point to
+///
+/// the vertical faces are updated with the new horizontal speeds, which keeps
+/// the speed of the inertial oscillations from growing
+pub(crate) fn rotate_faces<T: Scalar, L: Layout>(
+    config: &SimConfig,
+    horizontal_speeds: &mut Grid<T, L>,
+    vertical_values: &mut Grid<T, L>,
+    is_open: impl Fn(usize, usize, (usize, usize)) -> bool + Sync,
+    delta: T,
+) {
+    if config.rotation_rate == 0.0 {
+        return;
+    }
+    let h = T::from_f32(config.cell_size);
+    let half = T::from_f32(0.5);
+    let quarter = T::from_f32(0.25);
+    let (width, height) = horizontal_speeds.size();
+    let center = (
+        T::from_usize(width) * h * half,
+        T::from_usize(height) * h * half,
+    );
+
+    let v = &*vertical_values;
+    horizontal_speeds
+        .as_mut_slice()
+        .par_iter_mut()
+        .enumerate()
+        .for_each(|(index, speed)| {
+            let (x, y) = v.position(index);
+            if !is_open(x, y, (1, 0)) {
+                return;
+            }
+            let offset = (
+                T::from_usize(x) * h - center.0,
+                (T::from_usize(y) + half) * h - center.1,
+            );
+            let across = (v[(x - 1, y)] + v[(x - 1, y + 1)] + v[(x, y)] + v[(x, y + 1)]) * quarter;
+            *speed += config.frame_acceleration(offset, (*speed, across)).0 * delta;
+        });
+
+    let u = &*horizontal_speeds;
+    vertical_values
+        .as_mut_slice()
+        .par_iter_mut()
+        .enumerate()
+        .for_each(|(index, speed)| {
+            let (x, y) = u.position(index);
+            if !is_open(x, y, (0, 1)) {
+                return;
+            }
+            let offset = (
+                (T::from_usize(x) + half) * h - center.0,
+                T::from_usize(y) * h - center.1,
+            );
+            let across = (u[(x, y - 1)] + u[(x + 1, y - 1)] + u[(x, y)] + u[(x + 1, y)]) * quarter;
+            *speed += config.frame_acceleration(offset, (across, *speed)).1 * delta;
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SimConfig;
+
+    use super::*;
+
+    /// a sim with a square obstacle of 4 blocks centered between the top and bottom walls
+    fn sim_around_square(config: SimConfig) -> FluidSim<f64> {
+        let mut sim = FluidSim::new(60, 30, config);
+        for x in 20..24 {
+            for y in 13..17 {
+                sim.set_block(x, y);
+            }
+        }
+        sim
+    }
+
+    #[test]
+    fn the_blocks_touching_by_a_face_make_one_obstacle() {
+        let mut sim = sim_around_square(SimConfig::default());
+        // touching by a corner only
+        sim.set_block(24, 17);
+
+        let mut obstacle = sim.obstacle_at(21, 14);
+        obstacle.sort();
+        let square: Vec<_> = (20..24)
+            .flat_map(|x| (13..17).map(move |y| (x, y)))
+            .collect();
+        assert_eq!(obstacle, square);
+        assert_eq!(sim.obstacle_at(24, 17), vec![(24, 17)]);
+        assert!(sim.obstacle_at(30, 14).is_empty());
+    }
+
+    #[test]
+    fn the_wind_drags_a_symmetric_obstacle_without_lift() {
+        let mut sim = sim_around_square(SimConfig::default());
+        for _ in 0..40 {
+            sim.step(0.01);
+        }
+
+        let forces = sim.obstacle_forces(&sim.obstacle_at(21, 14));
+        assert!(forces.drag > 0.0, "drag {}", forces.drag);
+        // the sweeps of the pressure solve go upwards so the flow is only nearly symmetric
+        assert!(
+            forces.lift.abs() < 0.1 * forces.drag,
+            "lift {}",
+            forces.lift
+        );
+        assert!(forces.drag_coefficient > 0.0 && forces.drag_coefficient.is_finite());
+    }
+
+    #[test]
+    fn the_fluid_at_rest_has_no_force() {
+        let config = SimConfig {
+            wind_speed: 0.0,
+            ..SimConfig::default()
+        };
+        let mut sim = sim_around_square(config);
+        for _ in 0..10 {
+            sim.step(0.01);
+        }
+
+        let forces = sim.obstacle_forces(&sim.obstacle_at(21, 14));
+        assert!(forces.drag.abs() < 1e-6 && forces.lift.abs() < 1e-6);
+        // the coefficients are not defined without wind
+        assert_eq!(
+            (forces.drag_coefficient, forces.lift_coefficient),
+            (0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn the_strouhal_number_uses_the_frontal_height() {
+        let config = SimConfig {
+            wind_speed: 0.5,
+            cell_size: 0.01,
+            ..SimConfig::default()
+        };
+        let sim = sim_around_square(config);
+        // 4 rows of 1 cm shedding at 2.5 Hz in a wind of 0.5 m/s
+        let strouhal_number = sim.strouhal_number(2.5, &sim.obstacle_at(21, 14));
+        assert!((strouhal_number - 0.2).abs() < 1e-6);
+
+        let still = sim_around_square(SimConfig {
+            wind_speed: 0.0,
+            ..SimConfig::default()
+        });
+        assert_eq!(still.strouhal_number(2.5, &still.obstacle_at(21, 14)), 0.0);
+    }
+
+    #[test]
+    fn the_coriolis_force_turns_a_uniform_flow_without_changing_its_speed() {
+        let config = SimConfig {
+            rotation_rate: 1.0,
+            cell_size: 0.01,
+            ..SimConfig::default()
+        };
+        let (width, height) = (64, 64);
+        let mut horizontal_speeds = Grid::<f64>::new(width, height, 1.0);
+        let mut vertical_values = Grid::<f64>::new(width, height, 0.0);
+        let is_open = |x: usize, y: usize, _| x >= 1 && y >= 1 && x + 1 < width && y + 1 < height;
+        let (steps, delta) = (20, 0.02);
+        for _ in 0..steps {
+            rotate_faces(
+                &config,
+                &mut horizontal_speeds,
+                &mut vertical_values,
+                is_open,
+                delta,
+            );
+        }
+
+        // the faces next to the center, far from the border the mean across spreads from
+        let (x, y) = (width / 2, height / 2);
+        let u = horizontal_speeds[(x, y)];
+        let v = (vertical_values[(x - 1, y)]
+            + vertical_values[(x - 1, y + 1)]
+            + vertical_values[(x, y)]
+            + vertical_values[(x, y + 1)])
+            / 4.0;
+        assert!((u.hypot(v) - 1.0).abs() < 1e-2, "speed {}", u.hypot(v));
+        // clockwise by 2Ωt for a counterclockwise rotation
+        let angle = -2.0 * f64::from(config.rotation_rate) * steps as f64 * delta;
+        assert!(
+            (v.atan2(u) - angle).abs() < 2e-2,
+            "angle {} instead of {angle}",
+            v.atan2(u)
+        );
+    }
+}
@@ -0,0 +1,439 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+
+/// how the cells of a [`Grid`] are ordered in memory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GridLayout {
+    /// column by column, `(x, y)` is at `x * height + y`
+    #[default]
+    ColumnMajor,
+
+    /// row by row, `(x, y)` is at `y * width + x`
+    RowMajor,
+
+    /// square tiles of `size` cells that are each stored column by column
+    ///
+    /// the tiles are stored bottom to top in strips of `size` columns,
+    /// the tiles on the top and right edges are cut to fit the grid
+    Tiled { size: usize },
+}
+
+impl GridLayout {
+    /// returns the position of `(x, y)` in the storage of a grid of the given size
+    #[inline(always)]
+    fn index(self, x: usize, y: usize, width: usize, height: usize) -> usize {
+        match self {
+            GridLayout::ColumnMajor => x * height + y,
+            GridLayout::RowMajor => y * width + x,
+            GridLayout::Tiled { size } => {
+                let (strip, local_x) = (x / size, x % size);
+                let (tile, local_y) = (y / size, y % size);
+                let strip_width = size.min(width - strip * size);
+                let tile_height = size.min(height - tile * size);
+                strip * size * height + tile * size * strip_width + local_x * tile_height + local_y
+            }
+        }
+    }
+
+    /// returns the `(x, y)` coordinates of a position in the storage of a grid of the given size
+    #[inline(always)]
+    fn position(self, index: usize, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            GridLayout::ColumnMajor => (index / height, index % height),
+            GridLayout::RowMajor => (index % width, index / width),
+            GridLayout::Tiled { size } => {
+                let (strip, in_strip) = (index / (size * height), index % (size * height));
+                let strip_width = size.min(width - strip * size);
+                let (tile, in_tile) = (
+                    in_strip / (size * strip_width),
+                    in_strip % (size * strip_width),
+                );
+                let tile_height = size.min(height - tile * size);
+                (
+                    strip * size + in_tile / tile_height,
+                    tile * size + in_tile % tile_height,
+                )
+            }
+        }
+    }
+}
+
+/// the memory layout of a [`Grid`] chosen at compile time
+///
+/// the layout is a type so the index math of every access is known by the compiler,
+/// matching on a [`GridLayout`] for every cell makes the simulation about 40% slower
+pub trait Layout: Copy + Default + Debug + PartialEq + Send + Sync + 'static {
+    const LAYOUT: GridLayout;
+}
+
+/// see [`GridLayout::ColumnMajor`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ColumnMajor;
+
+/// see [`GridLayout::RowMajor`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RowMajor;
+
+/// see [`GridLayout::Tiled`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tiled<const SIZE: usize>;
+
+impl Layout for ColumnMajor {
+    const LAYOUT: GridLayout = GridLayout::ColumnMajor;
+}
+
+impl Layout for RowMajor {
+    const LAYOUT: GridLayout = GridLayout::RowMajor;
+}
+
+impl<const SIZE: usize> Layout for Tiled<SIZE> {
+    const LAYOUT: GridLayout = {
+        assert!(SIZE > 0, "the tiles need at least one cell");
+        GridLayout::Tiled { size: SIZE }
+    };
+}
+
+/// a 2d grid of values indexed by `(x, y)` with the origin at the bottom left
+///
+/// the values are stored column by column unless another [`Layout`] is chosen
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Grid<T, L: Layout = ColumnMajor> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+    layout: PhantomData<L>,
+}
+
+impl<T: Clone, L: Layout> Grid<T, L> {
+    /// creates a grid with every cell set to `value`
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Self {
+            cells: vec![value; width * height],
+            width,
+            height,
+            layout: PhantomData,
+        }
+    }
+
+    /// changes the size of the grid, the cells that are still in the grid keep their value
+    /// and the new ones are set to `value`
+    pub fn resize(&mut self, width: usize, height: usize, value: T) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        let mut resized = Self::new(width, height, value);
+        for x in 0..width.min(self.width) {
+            for y in 0..height.min(self.height) {
+                resized[(x, y)] = self[(x, y)].clone();
+            }
+        }
+        *self = resized;
+    }
+
+    #[inline]
+    pub fn fill(&mut self, value: T) {
+        self.cells.fill(value);
+    }
+}
+
+impl<T, L: Layout> Grid<T, L> {
+    /// creates a grid with the value of every cell computed from its `(x, y)` coordinates
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(usize, usize) -> T) -> Self {
+        let mut grid = Self {
+            cells: Vec::with_capacity(width * height),
+            width,
+            height,
+            layout: PhantomData,
+        };
+        let cells = grid.positions().map(|(x, y)| f(x, y)).collect();
+        grid.cells = cells;
+        grid
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// the number of cells in the grid
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    #[inline]
+    pub fn layout(&self) -> GridLayout {
+        L::LAYOUT
+    }
+
+    #[inline]
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// returns the position of `(x, y)` in the underlying storage
+    #[inline]
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        L::LAYOUT.index(x, y, self.width, self.height)
+    }
+
+    /// returns the `(x, y)` coordinates of a position in the underlying storage
+    #[inline]
+    pub fn position(&self, index: usize) -> (usize, usize) {
+        L::LAYOUT.position(index, self.width, self.height)
+    }
+
+    /// iterates over every `(x, y)` coordinate in storage order
+    ///
+    /// walking a grid in this order is the most cache friendly for its layout
+    pub fn positions(&self) -> Positions {
+        let (width, height) = (self.width, self.height);
+        // every layout is a walk over tiles that are each walked column by column
+        let (tile_width, tile_height) = match L::LAYOUT {
+            GridLayout::ColumnMajor => (1, height.max(1)),
+            GridLayout::RowMajor => (width.max(1), 1),
+            GridLayout::Tiled { size } => (size, size),
+        };
+        Positions {
+            width,
+            height: if width == 0 { 0 } else { height },
+            tile_width,
+            tile_height,
+            strip: 0,
+            tile: 0,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    /// bounds checked access to a cell
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.contains(x, y).then(|| &self.cells[self.index(x, y)])
+    }
+
+    /// bounds checked mutable access to a cell
+    #[inline]
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if !self.contains(x, y) {
+            return None;
+        }
+        let index = self.index(x, y);
+        Some(&mut self.cells[index])
+    }
+
+    /// returns the cells around `(x, y)` in the top, right, bottom, left order,
+    /// `None` for the ones outside of the grid
+    pub fn neighbors(&self, x: usize, y: usize) -> [Option<&T>; 4] {
+        [
+            self.get(x, y + 1),
+            self.get(x + 1, y),
+            y.checked_sub(1).and_then(|y| self.get(x, y)),
+            x.checked_sub(1).and_then(|x| self.get(x, y)),
+        ]
+    }
+
+    /// iterates over the cells of the column `x` from bottom to top
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        (0..self.height).map(move |y| &self[(x, y)])
+    }
+
+    /// mutably iterates over the cells of the column `x` from bottom to top
+    pub fn column_mut(&mut self, x: usize) -> Box<dyn Iterator<Item = &mut T> + '_> {
+        let (width, height) = (self.width, self.height);
+        match L::LAYOUT {
+            GridLayout::ColumnMajor => {
+                Box::new(self.cells[x * height..(x + 1) * height].iter_mut())
+            }
+            GridLayout::RowMajor => Box::new(self.cells.iter_mut().skip(x).step_by(width)),
+            // the cells of a column are in storage order from bottom to top in every tile
+            GridLayout::Tiled { .. } => Box::new(
+                self.cells
+                    .iter_mut()
+                    .enumerate()
+                    .filter(move |(index, _)| L::LAYOUT.position(*index, width, height).0 == x)
+                    .map(|(_, cell)| cell),
+            ),
+        }
+    }
+
+    /// iterates over the cells of the row `y` from left to right
+    pub fn row(&self, y: usize) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        (0..self.width).map(move |x| &self[(x, y)])
+    }
+
+    /// iterates over the columns from left to right
+    pub fn columns(&self) -> impl Iterator<Item = impl DoubleEndedIterator<Item = &T>> + '_ {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// iterates over the rows from bottom to top
+    pub fn rows(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = impl DoubleEndedIterator<Item = &T>> + '_ {
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    /// iterates over every cell in storage order
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+
+    /// mutably iterates over every cell in storage order
+    #[inline]
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.cells.iter_mut()
+    }
+
+    /// the cells in storage order, use [`Grid::index`] to find a cell
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// the mutable cells in storage order, use [`Grid::index`] to find a cell
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+}
+
+/// the position is only checked in debug builds to keep the hot loops fast, in release builds
+/// a position outside of the grid panics past the end of the storage but can land on another
+/// cell before it, use [`Grid::get`] or [`Grid::get_mut`] for positions that may be outside
+impl<T, L: Layout> Index<(usize, usize)> for Grid<T, L> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        debug_assert!(self.contains(x, y), "({x}, {y}) is outside of the grid");
+        &self.cells[Grid::index(self, x, y)]
+    }
+}
+
+impl<T, L: Layout> IndexMut<(usize, usize)> for Grid<T, L> {
+    #[inline]
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        debug_assert!(self.contains(x, y), "({x}, {y}) is outside of the grid");
+        let index = Grid::index(self, x, y);
+        &mut self.cells[index]
+    }
+}
+
+/// an iterator over the coordinates of a grid in storage order, see [`Grid::positions`]
+#[derive(Clone, Debug)]
+pub struct Positions {
+    width: usize,
+    height: usize,
+    tile_width: usize,
+    tile_height: usize,
+    /// the first column of the current strip of tiles
+    strip: usize,
+    /// the first row of the current tile
+    tile: usize,
+    x: usize,
+    y: usize,
+}
+
+impl Iterator for Positions {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.tile >= self.height {
+            return None;
+        }
+        let position = (self.x, self.y);
+
+        self.y += 1;
+        if self.y == (self.tile + self.tile_height).min(self.height) {
+            self.y = self.tile;
+            self.x += 1;
+            if self.x == (self.strip + self.tile_width).min(self.width) {
+                self.tile += self.tile_height;
+                if self.tile >= self.height {
+                    self.strip += self.tile_width;
+                    if self.strip < self.width {
+                        self.tile = 0;
+                    }
+                }
+                self.x = self.strip;
+                self.y = self.tile;
+            }
+        }
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// checks that the indexing, the storage positions and the iterators agree on every cell
+    fn check_layout<L: Layout>(width: usize, height: usize) {
+        // the cells are filled in the order of `positions`
+        let mut grid = Grid::<(usize, usize), L>::from_fn(width, height, |x, y| (x, y));
+        assert_eq!(grid.len(), width * height);
+        for (index, (x, y)) in grid.positions().enumerate() {
+            assert_eq!(grid.index(x, y), index, "{:?} {width}x{height}", L::LAYOUT);
+            assert_eq!(grid.position(index), (x, y));
+        }
+        assert_eq!(grid.positions().count(), width * height);
+
+        for x in 0..width {
+            for y in 0..height {
+                assert_eq!(grid[(x, y)], (x, y));
+            }
+            assert!(grid.column(x).copied().eq((0..height).map(|y| (x, y))));
+            assert!(grid
+                .column_mut(x)
+                .map(|cell| *cell)
+                .eq((0..height).map(|y| (x, y))));
+        }
+        for y in 0..height {
+            assert!(grid.row(y).copied().eq((0..width).map(|x| (x, y))));
+        }
+    }
+
+    fn check_sizes<L: Layout>() {
+        for (width, height) in [(0, 3), (1, 1), (7, 5), (16, 16), (17, 33), (40, 9)] {
+            check_layout::<L>(width, height);
+        }
+    }
+
+    #[test]
+    fn every_layout_finds_the_cells_it_stores() {
+        check_sizes::<ColumnMajor>();
+        check_sizes::<RowMajor>();
+        // the tiles on the top and right edges are cut for most of the sizes
+        check_sizes::<Tiled<4>>();
+        check_sizes::<Tiled<16>>();
+    }
+
+    #[test]
+    fn resizing_keeps_the_cells_of_a_cut_tiled_grid() {
+        let mut grid = Grid::<(usize, usize), Tiled<4>>::from_fn(10, 6, |x, y| (x, y));
+        grid.resize(7, 9, (0, 0));
+        for (x, y) in grid.positions() {
+            let expected = if y < 6 { (x, y) } else { (0, 0) };
+            assert_eq!(grid[(x, y)], expected);
+        }
+    }
+}
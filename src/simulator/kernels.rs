@@ -0,0 +1,299 @@
+//! vectorized versions of the hot loops of the simulator, enabled with the `simd` feature
+//!
+//! the loops work on whole columns, which are contiguous in memory, with equally sized
+//! slices and without branches so the compiler can auto vectorize them
+//!
+//! the projection corrects all the cells of one color of a checkerboard before the other
+//! color, the scalar loops go in storage order, so the results of the feature differ
+//! slightly from the ones without it, [`FluidSim::set_vectorized`](crate::FluidSim::set_vectorized)
+//! runs the scalar loops to compare them
+
+use rayon::prelude::*;
+
+use crate::Scalar;
+
+/// adds gravity to every vertical value that is not a block or on the border
+pub(crate) fn add_gravity<T: Scalar>(
+    vertical_values: &mut [T],
+    block_grid: &[bool],
+    height: usize,
+    gravity: T,
+) {
+    let width = vertical_values.len() / height;
+    vertical_values
+        .par_chunks_mut(height)
+        .zip(block_grid.par_chunks(height))
+        .enumerate()
+        .filter(|(x, _)| *x != 0 && *x != width - 1)
+        .for_each(|(_, (column, blocks))| {
+            let n = height - 2;
+            let (column, blocks) = (&mut column[1..=n], &blocks[1..=n]);
+            for j in 0..n {
+                column[j] += if blocks[j] { T::ZERO } else { gravity };
+            }
+        });
+}
+
+/// the buffers needed by the red black projection, kept between steps to avoid allocating
+#[derive(Default)]
+pub(crate) struct Projection<T: Scalar> {
+    /// 1 for fluid cells and 0 for blocks
+    fluid: Vec<T>,
+
+    /// overlax divided by the number of fluid neighbours for the cells of one color
+    /// of the checkerboard and 0 for every other cell
+    scales: [Vec<T>; 2],
+
+    /// the corrections of the column being projected
+    corrections: Vec<T>,
+
+    width: usize,
+    height: usize,
+}
+
+impl<T: Scalar> Projection<T> {
+    /// computes the fluid factors and the scales of every cell for the current blocks
+    pub(crate) fn prepare(&mut self, block_grid: &[bool], width: usize, height: usize, overlax: T) {
+        self.width = width;
+        self.height = height;
+        let size = width * height;
+
+        self.fluid.clear();
+        self.fluid.extend(
+            block_grid
+                .iter()
+                .map(|&is_block| if is_block { T::ZERO } else { T::ONE }),
+        );
+
+        let fluid = &self.fluid;
+        for (color, scales) in self.scales.iter_mut().enumerate() {
+            scales.clear();
+            scales.resize(size, T::ZERO);
+            for x in 1..width - 1 {
+                for y in 1..height - 1 {
+                    let index = x * height + y;
+                    if (x + y) % 2 != color || block_grid[index] {
+                        continue;
+                    }
+                    let number_of_fluids = fluid[index - height]
+                        + fluid[index + height]
+                        + fluid[index - 1]
+                        + fluid[index + 1];
+                    if number_of_fluids > T::ZERO {
+                        scales[index] = overlax / number_of_fluids;
+                    }
+                }
+            }
+        }
+        self.corrections.resize(height, T::ZERO);
+    }
+
+    /// removes the divergence of the velocities with red black gauss seidel iterations
+    ///
+    /// cells of the same color do not share any face so every column of a color
+    /// can be corrected at once instead of cell by cell
+    pub(crate) fn project(
+        &mut self,
+        horizontal_speeds: &mut [T],
+        vertical_values: &mut [T],
+        pressure_grid: &mut [T],
+        pressure_constant: T,
+        iterations: usize,
+    ) {
+        let (width, height) = (self.width, self.height);
+        let n = height - 2;
+
+        for _ in 0..iterations {
+            for scales in &self.scales {
+                for x in 1..width - 1 {
+                    let base = x * height;
+                    let (left, right) = horizontal_speeds.split_at_mut(base + height);
+                    let u = &mut left[base + 1..=base + n];
+                    let u_right = &mut right[1..=n];
+                    let v = &mut vertical_values[base..base + height];
+                    let pressure = &mut pressure_grid[base + 1..=base + n];
+                    let scales = &scales[base + 1..=base + n];
+                    let fluid_left = &self.fluid[base - height + 1..=base - height + n];
+                    let fluid_right = &self.fluid[base + height + 1..=base + height + n];
+                    let fluid_bottom = &self.fluid[base..base + n];
+                    let fluid_top = &self.fluid[base + 2..base + 2 + n];
+                    let corrections = &mut self.corrections[..n];
+
+                    {
+                        let (v, v_top) = (&v[1..=n], &v[2..n + 2]);
+                        for j in 0..n {
+                            let divergence = u_right[j] - u[j] + v_top[j] - v[j];
+                            corrections[j] = -scales[j] * divergence;
+                        }
+                    }
+
+                    for j in 0..n {
+                        u[j] -= corrections[j] * fluid_left[j];
+                        u_right[j] += corrections[j] * fluid_right[j];
+                        pressure[j] += pressure_constant * corrections[j];
+                    }
+
+                    let v_bottom = &mut v[1..=n];
+                    for j in 0..n {
+                        v_bottom[j] -= corrections[j] * fluid_bottom[j];
+                    }
+                    let v_top = &mut v[2..n + 2];
+                    for j in 0..n {
+                        v_top[j] += corrections[j] * fluid_top[j];
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// where the values advected by [`Advection`] are stored in the cells
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Samples {
+    /// on the left face of every cell
+    Horizontal,
+
+    /// on the bottom face of every cell
+    Vertical,
+
+    /// at the center of every cell
+    Centered,
+}
+
+/// the buffers of the semi-Lagrangian advection, kept between steps to avoid allocating
+///
+/// the positions every value comes from are traced back along the velocities for whole columns
+/// at once, then the fields are sampled there. the fields stored at the same place share
+/// the traced positions. only the tracing vectorizes, the sampling gathers values from
+/// anywhere in the grid
+#[derive(Default)]
+pub(crate) struct Advection<T: Scalar> {
+    /// the row of every cell of a column
+    rows: Vec<T>,
+
+    /// the position in cells every value comes from, measured from the first sample of the field
+    sources_x: Vec<T>,
+    sources_y: Vec<T>,
+}
+
+impl<T: Scalar> Advection<T> {
+    /// traces back the values of the `samples` of every cell that is not on the border,
+    /// `ratio` is the time step divided by the cell size
+    pub(crate) fn trace(
+        &mut self,
+        horizontal_speeds: &[T],
+        vertical_values: &[T],
+        width: usize,
+        height: usize,
+        ratio: T,
+        samples: Samples,
+    ) {
+        let size = width * height;
+        self.sources_x.resize(size, T::ZERO);
+        self.sources_y.resize(size, T::ZERO);
+        if self.rows.len() != height {
+            self.rows = (0..height).map(T::from_usize).collect();
+        }
+
+        let n = height - 2;
+        let (half, quarter) = (T::from_f32(0.5), T::from_f32(0.25));
+        let (max_x, max_y) = (T::from_usize(width), T::from_usize(height));
+        // where the samples are in their cell and the offset of the first one
+        let (offset_x, offset_y) = match samples {
+            Samples::Horizontal => (T::ZERO, half),
+            Samples::Vertical => (half, T::ZERO),
+            Samples::Centered => (half, half),
+        };
+        let (u, v) = (horizontal_speeds, vertical_values);
+        let rows = &self.rows[1..=n];
+
+        self.sources_x
+            .par_chunks_mut(height)
+            .zip(self.sources_y.par_chunks_mut(height))
+            .enumerate()
+            .filter(|(x, _)| *x != 0 && *x != width - 1)
+            .for_each(|(x, (sources_x, sources_y))| {
+                let (sources_x, sources_y) = (&mut sources_x[1..=n], &mut sources_y[1..=n]);
+                let column = |values, x, shift| column(values, x * height + shift, n);
+                let (u_here, u_right) = (column(u, x, 1), column(u, x + 1, 1));
+                let (v_here, v_top) = (column(v, x, 1), column(v, x, 2));
+                let position_x = T::from_usize(x) + offset_x;
+                match samples {
+                    Samples::Horizontal => {
+                        let (v_left, v_left_top) = (column(v, x - 1, 1), column(v, x - 1, 2));
+                        for j in 0..n {
+                            let across =
+                                (v_left[j] + v_left_top[j] + v_top[j] + v_here[j]) * quarter;
+                            sources_x[j] = position_x - u_here[j] * ratio;
+                            sources_y[j] = rows[j] + offset_y - across * ratio;
+                        }
+                    }
+                    Samples::Vertical => {
+                        let (u_bottom, u_right_bottom) = (column(u, x, 0), column(u, x + 1, 0));
+                        for j in 0..n {
+                            let across = (u_bottom[j] + u_right_bottom[j] + u_right[j] + u_here[j])
+                                * quarter;
+                            sources_x[j] = position_x - across * ratio;
+                            sources_y[j] = rows[j] + offset_y - v_here[j] * ratio;
+                        }
+                    }
+                    Samples::Centered => {
+                        for j in 0..n {
+                            sources_x[j] = position_x - (u_here[j] + u_right[j]) * half * ratio;
+                            sources_y[j] =
+                                rows[j] + offset_y - (v_here[j] + v_top[j]) * half * ratio;
+                        }
+                    }
+                }
+                // the positions are kept inside the domain, then measured from the first sample
+                for j in 0..n {
+                    sources_x[j] = sources_x[j].max(T::ONE).min(max_x) - offset_x;
+                    sources_y[j] = sources_y[j].max(T::ONE).min(max_y) - offset_y;
+                }
+            });
+    }
+
+    /// samples `values` at the traced positions into `new_values`,
+    /// the blocks and the border keep the value already in `new_values`
+    pub(crate) fn sample(
+        &self,
+        values: &[T],
+        new_values: &mut [T],
+        block_grid: &[bool],
+        width: usize,
+        height: usize,
+    ) {
+        let n = height - 2;
+        new_values
+            .par_chunks_mut(height)
+            .enumerate()
+            .filter(|(x, _)| *x != 0 && *x != width - 1)
+            .for_each(|(x, column)| {
+                let base = x * height;
+                for j in 1..=n {
+                    if block_grid[base + j] {
+                        continue;
+                    }
+                    let (source_x, source_y) = (self.sources_x[base + j], self.sources_y[base + j]);
+                    // the positions are positive so truncating them floors them
+                    let left = source_x.to_usize().min(width - 1);
+                    let right = (left + 1).min(width - 1);
+                    let bottom = source_y.to_usize().min(height - 1);
+                    let top = (bottom + 1).min(height - 1);
+                    let ratio_x = source_x - T::from_usize(left);
+                    let ratio_y = source_y - T::from_usize(bottom);
+                    let (sx, sy) = (T::ONE - ratio_x, T::ONE - ratio_y);
+
+                    column[j] = sx * sy * values[left * height + bottom]
+                        + ratio_x * sy * values[right * height + bottom]
+                        + ratio_x * ratio_y * values[right * height + top]
+                        + sx * ratio_y * values[left * height + top];
+                }
+            });
+    }
+}
+
+/// the `n` values of a column starting at `start`
+fn column<T>(values: &[T], start: usize, n: usize) -> &[T] {
+    &values[start..start + n]
+}
@@ -0,0 +1,474 @@
+use rayon::prelude::*;
+use std::time::Instant;
+
+use super::diagnostics::{cell_diagnostics, cell_velocity_at};
+use crate::{
+    ColumnMajor, FluidSim, Grid, Layout, Scalar, Shading, SimConfig, Solver, StepDiagnostics,
+};
+
+/// the directions of the D2Q9 lattice, at rest first then the axes and the diagonals
+const DIRECTIONS: [(isize, isize); 9] = [
+    (0, 0),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (0, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+    (1, -1),
+];
+
+/// the weight of every direction in the equilibrium in 36ths, divided in the precision
+/// of the solver so they add up to 1 in `f64` too and the collisions keep the mass
+const WEIGHTS: [f32; 9] = [16.0, 4.0, 4.0, 4.0, 4.0, 1.0, 1.0, 1.0, 1.0];
+
+/// the direction going the opposite way of every direction, for the bounce back
+const OPPOSITES: [usize; 9] = [0, 3, 4, 1, 2, 7, 8, 5, 6];
+
+/// the speed of the inflow in lattice units, low enough to keep the fluid almost incompressible
+const LATTICE_WIND_SPEED: f32 = 0.1;
+
+/// the lowest wind speed in m/s used to choose the time step, so the lattice still moves without wind
+const MIN_REFERENCE_SPEED: f32 = 0.05;
+
+/// the lowest relaxation time, the collisions are unstable when it gets close to 0.5
+const MIN_RELAXATION_TIME: f32 = 0.52;
+
+/// the most lattice steps taken in one step of the sim
+const MAX_LATTICE_STEPS: usize = 32;
+
+/// a lattice Boltzmann solver with the D2Q9 lattice and the BGK collision
+///
+/// every cell holds the density of particles going in each of the 9 directions,
+/// they collide towards the equilibrium then stream to the next cells and bounce back on the blocks.
+/// the lattice units are converted so its fields are filled in m/s and Pa like the ones of
+/// [`FluidSim`](crate::FluidSim), the wind blows from the left and the top and bottom of the domain are walls
+pub struct LatticeBoltzmann<T: Scalar = f32, L: Layout = ColumnMajor> {
+    distributions: Grid<[T; 9], L>,
+
+    /// the distributions are streamed into it before being swapped
+    buffer: Grid<[T; 9], L>,
+
+    /// the horizontal speeds in m/s, stored on the left face of every cell
+    horizontal_speeds: Grid<T, L>,
+
+    /// the vertical speeds in m/s, stored on the bottom face of every cell
+    vertical_values: Grid<T, L>,
+
+    /// the velocity in m/s at the center of every cell
+    velocity_grid: Grid<(T, T), L>,
+
+    /// the pressure in Pa from the density of the lattice
+    pressure_grid: Grid<T, L>,
+
+    smoke_grid: Grid<T, L>,
+
+    /// the smoke is advected into it before being swapped
+    smoke_buffer: Grid<T, L>,
+    block_grid: Grid<bool, L>,
+
+    /// the simulated time that did not add up to a full lattice step yet
+    pending_time: T,
+
+    width: usize,
+    height: usize,
+
+    // the last time the lattice has been calculated
+    last_instant: Instant,
+
+    config: SimConfig,
+}
+
+impl<T: Scalar, L: Layout> Default for LatticeBoltzmann<T, L> {
+    fn default() -> Self {
+        LatticeBoltzmann::new(2, 2, SimConfig::default())
+    }
+}
+
+impl<T: Scalar, L: Layout> LatticeBoltzmann<T, L> {
+    /// starts with the fluid at rest except for the inflow
+    pub fn new(width: usize, height: usize, mut config: SimConfig) -> Self {
+        config.fit_domain(width);
+        let zeros = Grid::new(width, height, T::ZERO);
+        let at_rest = equilibrium(T::ONE, (T::ZERO, T::ZERO));
+        let mut block_grid = Grid::new(width, height, false);
+        // the left border is a block like the one of the staggered grid
+        block_grid.column_mut(0).for_each(|cell| *cell = true);
+        let mut lattice = Self {
+            distributions: Grid::new(width, height, at_rest),
+            buffer: Grid::new(width, height, at_rest),
+            horizontal_speeds: zeros.clone(),
+            vertical_values: zeros.clone(),
+            velocity_grid: Grid::new(width, height, (T::ZERO, T::ZERO)),
+            pressure_grid: zeros,
+            smoke_grid: Grid::new(width, height, T::ONE),
+            smoke_buffer: Grid::new(width, height, T::ONE),
+            block_grid,
+            pending_time: T::ZERO,
+            width,
+            height,
+            last_instant: Instant::now(),
+            config,
+        };
+        FluidSim::set_smoke_pipe(&mut lattice.smoke_grid, lattice.config.smoke_size);
+        lattice.set_inflow();
+        lattice.write_fields();
+        lattice
+    }
+
+    /// the size of a lattice step in seconds and the speed in m/s of one lattice unit
+    fn scales(config: &SimConfig) -> (f32, f32) {
+        let speed_scale = config.wind_speed.max(MIN_REFERENCE_SPEED) / LATTICE_WIND_SPEED;
+        (config.cell_size / speed_scale, speed_scale)
+    }
+
+    /// the relaxation time of the collisions giving the viscosity of the config,
+    /// kept above [`MIN_RELAXATION_TIME`] which makes the fluid more viscous than asked
+    /// when the lattice is too coarse for it
+    fn relaxation_time(config: &SimConfig) -> f32 {
+        let (step_time, _) = Self::scales(config);
+        let viscosity = config.viscosity * step_time / (config.cell_size * config.cell_size);
+        (3.0 * viscosity + 0.5).max(MIN_RELAXATION_TIME)
+    }
+
+    /// takes as many lattice steps as fit in `delta` seconds and fills the fields
+    fn advance(&mut self, delta: T) {
+        let (step_time, _) = Self::scales(&self.config);
+        let step_time = T::from_f32(step_time);
+        let relaxation_time = Self::relaxation_time(&self.config);
+
+        self.pending_time += delta;
+        let steps = (self.pending_time / step_time)
+            .to_usize()
+            .min(MAX_LATTICE_STEPS);
+        // the time that could not be simulated is dropped so a slow frame does not pile up
+        self.pending_time = if steps == MAX_LATTICE_STEPS {
+            T::ZERO
+        } else {
+            self.pending_time - T::from_usize(steps) * step_time
+        };
+
+        for _ in 0..steps {
+            self.collide(T::from_f32(relaxation_time));
+            self.stream();
+            self.set_inflow();
+            self.move_smoke();
+        }
+        self.write_fields();
+    }
+
+    /// relaxes the distributions of every fluid cell towards their equilibrium
+    fn collide(&mut self, relaxation_time: T) {
+        let inverse_time = T::ONE / relaxation_time;
+        let blocks = &self.block_grid;
+        self.distributions
+            .as_mut_slice()
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, distribution)| {
+                let (x, y) = blocks.position(index);
+                if is_solid(blocks, x, y) {
+                    return;
+                }
+                let (density, velocity) = moments(distribution);
+                let equilibrium = equilibrium(density, velocity);
+                for (value, target) in distribution.iter_mut().zip(equilibrium) {
+                    *value += (target - *value) * inverse_time;
+                }
+            });
+    }
+
+    /// moves the distributions to the next cell in their direction,
+    /// the ones coming from a solid cell are the ones that bounced back on it
+    fn stream(&mut self) {
+        let blocks = &self.block_grid;
+        let (width, height) = blocks.size();
+        let distributions = &self.distributions;
+        self.buffer
+            .as_mut_slice()
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, streamed)| {
+                let (x, y) = blocks.position(index);
+                // the outflow is copied from the column before it
+                let x = x.min(width - 2);
+                if is_solid(blocks, x, y) {
+                    return;
+                }
+                for (direction, &(step_x, step_y)) in DIRECTIONS.iter().enumerate() {
+                    let source_x = x.wrapping_add_signed(-step_x);
+                    let source_y = y.wrapping_add_signed(-step_y);
+                    streamed[direction] = if source_x >= width
+                        || source_y >= height
+                        || is_solid(blocks, source_x, source_y)
+                    {
+                        distributions[(x, y)][OPPOSITES[direction]]
+                    } else {
+                        distributions[(source_x, source_y)][direction]
+                    };
+                }
+            });
+        std::mem::swap(&mut self.distributions, &mut self.buffer);
+    }
+
+    /// the cells next to the left border are kept at the equilibrium of the wind
+    fn set_inflow(&mut self) {
+        let (_, speed_scale) = Self::scales(&self.config);
+        let wind = T::from_f32(self.config.wind_speed / speed_scale);
+        let inflow = equilibrium(T::ONE, (wind, T::ZERO));
+        if self.distributions.width() > 1 {
+            self.distributions
+                .column_mut(1)
+                .for_each(|distribution| *distribution = inflow);
+        }
+    }
+
+    /// moves the smoke by a lattice step along the velocities at the cell centers
+    fn move_smoke(&mut self) {
+        let blocks = &self.block_grid;
+        let (width, height) = blocks.size();
+        let distributions = &self.distributions;
+        let smoke_values = &self.smoke_grid;
+        self.smoke_buffer
+            .as_mut_slice()
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, new_smoke)| {
+                let (x, y) = blocks.position(index);
+                if is_solid(blocks, x, y) {
+                    *new_smoke = smoke_values[(x, y)];
+                    return;
+                }
+                let (_, (u, v)) = moments(&distributions[(x, y)]);
+                let source_x = (T::from_usize(x) - u)
+                    .max(T::ZERO)
+                    .min(T::from_usize(width - 1));
+                let source_y = (T::from_usize(y) - v)
+                    .max(T::ZERO)
+                    .min(T::from_usize(height - 1));
+                *new_smoke = sample(smoke_values, source_x, source_y);
+            });
+        std::mem::swap(&mut self.smoke_grid, &mut self.smoke_buffer);
+    }
+
+    /// converts the lattice velocities and densities to the face speeds and the pressure
+    fn write_fields(&mut self) {
+        let (_, speed_scale) = Self::scales(&self.config);
+        let blocks = &self.block_grid;
+        let half = T::from_f32(0.5);
+        let speed_scale = T::from_f32(speed_scale);
+        // the lattice pressure is the density difference times the squared speed of sound, 1/3
+        let pressure_scale = T::from_f32(self.config.density / 3.0) * speed_scale * speed_scale;
+
+        let velocity = |x: usize, y: usize| {
+            if is_solid(blocks, x, y) {
+                (T::ZERO, T::ZERO)
+            } else {
+                moments(&self.distributions[(x, y)]).1
+            }
+        };
+        for (x, y) in blocks.positions() {
+            let (u, v) = velocity(x, y);
+            let (left_u, _) = velocity(x.saturating_sub(1), y);
+            let (_, bottom_v) = velocity(x, y.saturating_sub(1));
+            self.horizontal_speeds[(x, y)] = (u + left_u) * half * speed_scale;
+            self.vertical_values[(x, y)] = (v + bottom_v) * half * speed_scale;
+            self.velocity_grid[(x, y)] = (u * speed_scale, v * speed_scale);
+
+            let (density, _) = moments(&self.distributions[(x, y)]);
+            self.pressure_grid[(x, y)] = if is_solid(blocks, x, y) {
+                T::ZERO
+            } else {
+                (density - T::ONE) * pressure_scale
+            };
+        }
+    }
+
+    /// computes the diagnostics of the current fields over the fluid cells inside the border
+    pub fn diagnostics(&self) -> StepDiagnostics<T> {
+        let (width, height) = (self.width, self.height);
+        cell_diagnostics(&self.velocity_grid, &self.config, |x, y| {
+            let is_border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            (!is_border && !self.block_grid[(x, y)])
+                .then(|| (T::ONE - self.smoke_grid[(x, y)], T::ONE))
+        })
+    }
+}
+
+impl<T: Scalar, L: Layout> Solver<T, L> for LatticeBoltzmann<T, L> {
+    fn name(&self) -> &'static str {
+        "Lattice Boltzmann"
+    }
+
+    fn next_step(&mut self) -> StepDiagnostics<T> {
+        self.advance(T::from_f64(self.last_instant.elapsed().as_secs_f64()));
+        self.last_instant = Instant::now();
+        self.diagnostics()
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        let mut block_grid = std::mem::take(&mut self.block_grid);
+        block_grid.resize(width, height, false);
+        // set the left border for the new height
+        block_grid.column_mut(0).for_each(|cell| *cell = true);
+
+        *self = Self::new(width, height, self.config.clone());
+        self.block_grid = block_grid;
+        self.write_fields();
+    }
+
+    fn restart_sim(&mut self) {
+        self.resize(self.width, self.height);
+    }
+
+    fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn get_config(&self) -> &SimConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, mut config: SimConfig) {
+        config.fit_domain(self.width);
+        FluidSim::set_smoke_pipe(&mut self.smoke_grid, config.smoke_size);
+        self.config = config;
+    }
+
+    fn get_block_grid(&self) -> &Grid<bool, L> {
+        &self.block_grid
+    }
+
+    fn set_block(&mut self, x: usize, y: usize) {
+        if let Some(cell) = self.block_grid.get_mut(x, y) {
+            *cell = true;
+        }
+    }
+
+    fn unset_block(&mut self, x: usize, y: usize) {
+        if let Some(cell) = self.block_grid.get_mut(x, y) {
+            *cell = false;
+        }
+    }
+
+    fn get_pressure_grid(&self) -> &Grid<T, L> {
+        &self.pressure_grid
+    }
+
+    fn get_smoke_grid(&self) -> &Grid<T, L> {
+        &self.smoke_grid
+    }
+
+    fn velocity_at(&self, x: T, y: T) -> (T, T) {
+        cell_velocity_at(
+            &self.velocity_grid,
+            (x, y),
+            T::from_f32(self.config.cell_size),
+        )
+    }
+
+    fn shading(&self) -> Shading {
+        Shading::Smoke
+    }
+
+    fn effective_viscosity(&self) -> f32 {
+        let (step_time, _) = Self::scales(&self.config);
+        let cell_size = self.config.cell_size;
+        (Self::relaxation_time(&self.config) - 0.5) / 3.0 * cell_size * cell_size / step_time
+    }
+}
+
+/// the blocks and the top and bottom walls of the channel
+#[inline]
+fn is_solid<L: Layout>(blocks: &Grid<bool, L>, x: usize, y: usize) -> bool {
+    blocks[(x, y)] || y == 0 || y == blocks.height() - 1
+}
+
+/// returns the density and the velocity of the distributions of a cell
+#[inline]
+fn moments<T: Scalar>(distribution: &[T; 9]) -> (T, (T, T)) {
+    let mut density = T::ZERO;
+    let (mut momentum_x, mut momentum_y) = (T::ZERO, T::ZERO);
+    for (&value, &(step_x, step_y)) in distribution.iter().zip(&DIRECTIONS) {
+        density += value;
+        momentum_x += value * T::from_f32(step_x as f32);
+        momentum_y += value * T::from_f32(step_y as f32);
+    }
+    if density <= T::ZERO {
+        return (density, (T::ZERO, T::ZERO));
+    }
+    (density, (momentum_x / density, momentum_y / density))
+}
+
+/// the distributions of a fluid at rest in the frame moving at `velocity`
+#[inline]
+fn equilibrium<T: Scalar>(density: T, (u, v): (T, T)) -> [T; 9] {
+    let squared_speed = u * u + v * v;
+    std::array::from_fn(|direction| {
+        let (step_x, step_y) = DIRECTIONS[direction];
+        let projection = T::from_f32(step_x as f32) * u + T::from_f32(step_y as f32) * v;
+        T::from_f32(WEIGHTS[direction]) / T::from_f32(36.0)
+            * density
+            * (T::ONE + T::from_f32(3.0) * projection + T::from_f32(4.5) * projection * projection
+                - T::from_f32(1.5) * squared_speed)
+    })
+}
+
+/// interpolates a field stored at the cell centers at a position in cells
+fn sample<T: Scalar, L: Layout>(field: &Grid<T, L>, x: T, y: T) -> T {
+    let (width, height) = field.size();
+    let left = x.floor().to_usize().min(width.saturating_sub(2));
+    let bottom = y.floor().to_usize().min(height.saturating_sub(2));
+    let right = (left + 1).min(width - 1);
+    let top = (bottom + 1).min(height - 1);
+    let ratio_x = (x - T::from_usize(left)).max(T::ZERO).min(T::ONE);
+    let ratio_y = (y - T::from_usize(bottom)).max(T::ZERO).min(T::ONE);
+
+    (T::ONE - ratio_x) * (T::ONE - ratio_y) * field[(left, bottom)]
+        + ratio_x * (T::ONE - ratio_y) * field[(right, bottom)]
+        + (T::ONE - ratio_x) * ratio_y * field[(left, top)]
+        + ratio_x * ratio_y * field[(right, top)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the density summed over the fluid cells, in lattice units
+    fn total_density(lattice: &LatticeBoltzmann<f64>) -> f64 {
+        lattice
+            .block_grid
+            .positions()
+            .filter(|&(x, y)| !is_solid(&lattice.block_grid, x, y))
+            .map(|(x, y)| moments(&lattice.distributions[(x, y)]).0)
+            .sum()
+    }
+
+    #[test]
+    fn the_fluid_at_rest_stays_at_rest() {
+        let config = SimConfig {
+            wind_speed: 0.0,
+            ..SimConfig::default()
+        };
+        let mut lattice = LatticeBoltzmann::<f64>::new(40, 20, config);
+        for y in 8..12 {
+            lattice.block_grid[(20, y)] = true;
+        }
+        let mass = total_density(&lattice);
+        let (step_time, _) = LatticeBoltzmann::<f64>::scales(&lattice.config);
+        for _ in 0..10 {
+            lattice.advance(f64::from(step_time) * 10.0);
+        }
+
+        let diagnostics = lattice.diagnostics();
+        assert!(
+            diagnostics.max_speed < 1e-12,
+            "speed {}",
+            diagnostics.max_speed
+        );
+        assert!(diagnostics.kinetic_energy < 1e-12);
+        let lost = (total_density(&lattice) - mass).abs() / mass;
+        assert!(lost < 1e-12, "{lost} of the mass is lost");
+    }
+}
@@ -0,0 +1,579 @@
+use rayon::prelude::*;
+
+use crate::{ColumnMajor, Grid, Layout, Scalar};
+
+/// what fills a cell of the grid in the liquid mode
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CellType {
+    /// a cell with at least one particle, the pressure is solved in it
+    Fluid,
+
+    /// an empty cell, its pressure is 0
+    #[default]
+    Air,
+
+    /// a block or the border of the tank
+    Solid,
+}
+
+/// a small piece of liquid carrying its velocity between the steps
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LiquidParticle<T: Scalar = f32> {
+    /// the position in meters from the bottom left corner
+    pub position: (T, T),
+
+    /// the velocity in m/s
+    pub velocity: (T, T),
+}
+
+/// the particles of the liquid mode and the grids used to move their velocities
+/// to the staggered grid of the sim and back (PIC/FLIP)
+#[derive(Clone, Debug, Default)]
+pub struct Liquid<T: Scalar = f32, L: Layout = ColumnMajor> {
+    particles: Vec<LiquidParticle<T>>,
+    cell_types: Grid<CellType, L>,
+
+    /// the number of particles around the center of every cell, weighted by their distance
+    particle_density: Grid<T, L>,
+
+    /// the mean particle density of the fluid cells on the first step, the liquid is
+    /// pushed apart where it is denser than this
+    rest_density: T,
+
+    /// the sum of the weights of the particles on every face during the transfer to the grid
+    weights: Grid<T, L>,
+
+    /// the velocities of the grid before the pressure solve, for the FLIP update
+    previous_horizontal: Grid<T, L>,
+    previous_vertical: Grid<T, L>,
+
+    /// the fraction of liquid averaged over the cells around every cell, which smooths
+    /// the noise of the particles out of the surface
+    color: Grid<T, L>,
+
+    /// the unit normal of the surface at the center of every cell pointing into the liquid,
+    /// (0, 0) away from the surface
+    normals: Grid<(T, T), L>,
+
+    /// the curvature of the surface at the center of every cell in 1/m,
+    /// positive where the liquid bulges out
+    curvature: Grid<T, L>,
+}
+
+impl<T: Scalar, L: Layout> Liquid<T, L> {
+    /// the number of particles in each direction of a cell
+    const PARTICLES_PER_SIDE: usize = 2;
+
+    /// how strongly the pressure solve pushes apart compressed liquid
+    const DRIFT_STIFFNESS: f32 = 1.0;
+
+    /// fills the bottom left of the tank with liquid at rest, like a dam about to break
+    pub(crate) fn new(blocks: &Grid<bool, L>, cell_size: f32) -> Self {
+        let (width, height) = blocks.size();
+        let h = T::from_f32(cell_size);
+        let spacing = T::ONE / T::from_usize(Self::PARTICLES_PER_SIDE);
+        let half = T::from_f32(0.5);
+
+        let mut particles = Vec::new();
+        for (x, y) in blocks.positions() {
+            let is_dam = x * 5 < width * 2 && y * 5 < height * 3;
+            if !is_dam || is_solid(blocks, x, y) {
+                continue;
+            }
+            for i in 0..Self::PARTICLES_PER_SIDE {
+                for j in 0..Self::PARTICLES_PER_SIDE {
+                    let offset_x = (T::from_usize(i) + half) * spacing;
+                    let offset_y = (T::from_usize(j) + half) * spacing;
+                    particles.push(LiquidParticle {
+                        position: (
+                            (T::from_usize(x) + offset_x) * h,
+                            (T::from_usize(y) + offset_y) * h,
+                        ),
+                        velocity: (T::ZERO, T::ZERO),
+                    });
+                }
+            }
+        }
+
+        let zeros = Grid::new(width, height, T::ZERO);
+        Self {
+            particles,
+            cell_types: Grid::new(width, height, CellType::Air),
+            particle_density: zeros.clone(),
+            rest_density: T::ZERO,
+            weights: zeros.clone(),
+            previous_horizontal: zeros.clone(),
+            previous_vertical: zeros.clone(),
+            color: zeros.clone(),
+            normals: Grid::new(width, height, (T::ZERO, T::ZERO)),
+            curvature: zeros,
+        }
+    }
+
+    #[inline]
+    pub fn particles(&self) -> &[LiquidParticle<T>] {
+        &self.particles
+    }
+
+    #[inline]
+    pub fn cell_types(&self) -> &Grid<CellType, L> {
+        &self.cell_types
+    }
+
+    #[inline]
+    pub fn particle_density(&self) -> &Grid<T, L> {
+        &self.particle_density
+    }
+
+    /// the fraction of a cell filled with liquid in [0, 1]
+    pub fn fraction_at(&self, x: usize, y: usize) -> T {
+        fraction(self.particle_density[(x, y)], self.rest_density)
+    }
+
+    /// the largest distance a particle can move in `delta` seconds with an acceleration
+    /// of at most `max_acceleration`, to choose the substeps
+    pub(crate) fn max_travel(&self, max_acceleration: T, delta: T) -> T {
+        let max_speed = self
+            .particles
+            .par_iter()
+            .map(|particle| {
+                let (u, v) = particle.velocity;
+                (u * u + v * v).sqrt()
+            })
+            .reduce(|| T::ZERO, T::max);
+        (max_speed + max_acceleration * delta) * delta
+    }
+
+    /// accelerates the particles and moves them, stopping them on the solid cells
+    ///
+    /// `acceleration` gives the acceleration of a particle from its position and velocity
+    pub(crate) fn move_particles(
+        &mut self,
+        blocks: &Grid<bool, L>,
+        acceleration: impl Fn((T, T), (T, T)) -> (T, T) + Sync,
+        delta: T,
+        h: T,
+    ) {
+        let is_solid_at = |(x, y): (T, T)| {
+            x < T::ZERO || y < T::ZERO || {
+                let (i, j) = ((x / h).to_usize(), (y / h).to_usize());
+                !blocks.contains(i, j) || is_solid(blocks, i, j)
+            }
+        };
+
+        self.particles.par_iter_mut().for_each(|particle| {
+            let (ax, ay) = acceleration(particle.position, particle.velocity);
+            particle.velocity.0 += ax * delta;
+            particle.velocity.1 += ay * delta;
+            let (x, y) = particle.position;
+            let (u, v) = particle.velocity;
+            let (mut new_x, mut new_y) = (x + u * delta, y + v * delta);
+
+            // the velocity into a wall is lost, the liquid slides along it
+            if is_solid_at((new_x, new_y)) {
+                if is_solid_at((new_x, y)) {
+                    new_x = x;
+                    particle.velocity.0 = T::ZERO;
+                }
+                if is_solid_at((x, new_y)) {
+                    new_y = y;
+                    particle.velocity.1 = T::ZERO;
+                }
+                if is_solid_at((new_x, new_y)) {
+                    (new_x, new_y) = (x, y);
+                    particle.velocity = (T::ZERO, T::ZERO);
+                }
+            }
+            particle.position = (new_x, new_y);
+        });
+    }
+
+    /// marks the cells and averages the velocities of the particles on the faces of the grid
+    pub(crate) fn transfer_to_grid(
+        &mut self,
+        horizontal_speeds: &mut Grid<T, L>,
+        vertical_values: &mut Grid<T, L>,
+        blocks: &Grid<bool, L>,
+        h: T,
+    ) {
+        for (x, y) in blocks.positions() {
+            self.cell_types[(x, y)] = if is_solid(blocks, x, y) {
+                CellType::Solid
+            } else {
+                CellType::Air
+            };
+        }
+        for particle in &self.particles {
+            let (x, y) = particle.position;
+            let cell = ((x / h).to_usize(), (y / h).to_usize());
+            if self.cell_types.get(cell.0, cell.1) == Some(&CellType::Air) {
+                self.cell_types[cell] = CellType::Fluid;
+            }
+        }
+
+        let half = T::from_f32(0.5) * h;
+        let components = [
+            (&mut *horizontal_speeds, (T::ZERO, half), (1, 0)),
+            (&mut *vertical_values, (half, T::ZERO), (0, 1)),
+        ];
+        for (field, offset, (step_x, step_y)) in components {
+            field.fill(T::ZERO);
+            self.weights.fill(T::ZERO);
+            for particle in &self.particles {
+                let velocity = if step_x == 1 {
+                    particle.velocity.0
+                } else {
+                    particle.velocity.1
+                };
+                for (cell, weight) in stencil(field, particle.position, offset, h) {
+                    field[cell] += weight * velocity;
+                    self.weights[cell] += weight;
+                }
+            }
+
+            for (index, value) in field.as_mut_slice().iter_mut().enumerate() {
+                let (x, y) = blocks.position(index);
+                let weight = self.weights.as_slice()[index];
+                // the faces touching a solid cell do not move
+                let touches_solid = self.cell_types[(x, y)] == CellType::Solid
+                    || (x >= step_x
+                        && y >= step_y
+                        && self.cell_types[(x - step_x, y - step_y)] == CellType::Solid);
+                if touches_solid {
+                    *value = T::ZERO;
+                } else if weight > T::ZERO {
+                    *value /= weight;
+                }
+            }
+        }
+
+        self.previous_horizontal
+            .as_mut_slice()
+            .copy_from_slice(horizontal_speeds.as_slice());
+        self.previous_vertical
+            .as_mut_slice()
+            .copy_from_slice(vertical_values.as_slice());
+    }
+
+    /// counts the particles around every cell center
+    pub(crate) fn update_density(&mut self, h: T) {
+        let half = T::from_f32(0.5) * h;
+        self.particle_density.fill(T::ZERO);
+        for particle in &self.particles {
+            for (cell, weight) in
+                stencil(&self.particle_density, particle.position, (half, half), h)
+            {
+                self.particle_density[cell] += weight;
+            }
+        }
+
+        if self.rest_density == T::ZERO {
+            let (sum, count) = self
+                .cell_types
+                .iter()
+                .zip(self.particle_density.iter())
+                .filter(|(cell_type, _)| **cell_type == CellType::Fluid)
+                .fold((T::ZERO, 0), |(sum, count), (_, &density)| {
+                    (sum + density, count + 1)
+                });
+            if count > 0 {
+                self.rest_density = sum / T::from_usize(count);
+            }
+        }
+    }
+
+    /// pulls the surface of the liquid flat with the continuum surface force, which beads up
+    /// the drops and merges them when they touch
+    ///
+    /// the smoothed fraction of liquid in the cells marks the surface, its gradient gives the normal
+    /// and the divergence of the normal the curvature. every face is pushed by the curvature
+    /// times the jump of the fraction across it. `tension` is the surface tension divided by
+    /// the density in m³/s², the solid cells do not pull the liquid
+    pub(crate) fn add_surface_tension(
+        &mut self,
+        horizontal_speeds: &mut Grid<T, L>,
+        vertical_values: &mut Grid<T, L>,
+        tension: T,
+        delta: T,
+        h: T,
+    ) {
+        if tension <= T::ZERO {
+            return;
+        }
+        let half = T::from_f32(0.5);
+        let cell_types = &self.cell_types;
+        let (density, rest_density) = (&self.particle_density, self.rest_density);
+        // the central difference of a value of the cells,
+        // a solid neighbor has the value of the center
+        let difference = |(x, y): (usize, usize),
+                          (step_x, step_y): (usize, usize),
+                          value: &dyn Fn((usize, usize)) -> T| {
+            let center = value((x, y));
+            let around = |cell: (usize, usize)| {
+                if cell_types[cell] == CellType::Solid {
+                    center
+                } else {
+                    value(cell)
+                }
+            };
+            (around((x + step_x, y + step_y)) - around((x - step_x, y - step_y))) * half / h
+        };
+
+        self.color
+            .as_mut_slice()
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, color)| {
+                let (x, y) = cell_types.position(index);
+                *color = T::ZERO;
+                if cell_types[(x, y)] == CellType::Solid {
+                    return;
+                }
+                // the cells that are not solid are never on the border
+                let (mut sum, mut count) = (T::ZERO, T::ZERO);
+                for cell_x in x - 1..=x + 1 {
+                    for cell_y in y - 1..=y + 1 {
+                        if cell_types[(cell_x, cell_y)] != CellType::Solid {
+                            sum += fraction(density[(cell_x, cell_y)], rest_density);
+                            count += T::ONE;
+                        }
+                    }
+                }
+                *color = sum / count;
+            });
+
+        let color = &self.color;
+        self.normals
+            .as_mut_slice()
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, normal)| {
+                let cell = cell_types.position(index);
+                *normal = (T::ZERO, T::ZERO);
+                if cell_types[cell] == CellType::Solid {
+                    return;
+                }
+                let gradient = (
+                    difference(cell, (1, 0), &|cell| color[cell]),
+                    difference(cell, (0, 1), &|cell| color[cell]),
+                );
+                let length = (gradient.0 * gradient.0 + gradient.1 * gradient.1).sqrt();
+                // a jump of less than a thousandth of a cell is not a surface
+                if length * h > T::from_f32(1e-3) {
+                    *normal = (gradient.0 / length, gradient.1 / length);
+                }
+            });
+
+        let normals = &self.normals;
+        self.curvature
+            .as_mut_slice()
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, curvature)| {
+                let cell = cell_types.position(index);
+                *curvature = T::ZERO;
+                if cell_types[cell] == CellType::Solid || normals[cell] == (T::ZERO, T::ZERO) {
+                    return;
+                }
+                *curvature = -difference(cell, (1, 0), &|cell| normals[cell].0)
+                    - difference(cell, (0, 1), &|cell| normals[cell].1);
+            });
+
+        let curvature = &self.curvature;
+        let components = [
+            (&mut *horizontal_speeds, (1, 0)),
+            (&mut *vertical_values, (0, 1)),
+        ];
+        for (field, (step_x, step_y)) in components {
+            field
+                .as_mut_slice()
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(index, value)| {
+                    let cell = cell_types.position(index);
+                    if cell.0 < step_x || cell.1 < step_y {
+                        return;
+                    }
+                    let before = (cell.0 - step_x, cell.1 - step_y);
+                    if cell_types[cell] == CellType::Solid || cell_types[before] == CellType::Solid
+                    {
+                        return;
+                    }
+                    let jump = (color[cell] - color[before]) / h;
+                    let face_curvature = (curvature[cell] + curvature[before]) * half;
+                    *value += tension * face_curvature * jump * delta;
+                });
+        }
+    }
+
+    /// makes the velocities of the fluid cells divergence free with successive over relaxation,
+    /// the air cells keep a pressure of 0 so the liquid has a free surface
+    pub(crate) fn project(
+        &self,
+        horizontal_speeds: &mut Grid<T, L>,
+        vertical_values: &mut Grid<T, L>,
+        pressure_grid: &mut Grid<T, L>,
+        pressure_constant: T,
+        iterations: usize,
+        overlax: T,
+    ) {
+        pressure_grid.fill(T::ZERO);
+        let stiffness = T::from_f32(Self::DRIFT_STIFFNESS);
+        let open = |cell: (usize, usize)| {
+            if self.cell_types[cell] == CellType::Solid {
+                T::ZERO
+            } else {
+                T::ONE
+            }
+        };
+
+        for _ in 0..iterations {
+            for (i, j) in self.cell_types.positions() {
+                // fluid cells are never on the border which is solid
+                if self.cell_types[(i, j)] != CellType::Fluid {
+                    continue;
+                }
+                let (top, right, bottom, left) = ((i, j + 1), (i + 1, j), (i, j - 1), (i - 1, j));
+                let (top_open, right_open) = (open(top), open(right));
+                let (bottom_open, left_open) = (open(bottom), open(left));
+                let number_of_open = top_open + right_open + bottom_open + left_open;
+                if number_of_open == T::ZERO {
+                    continue;
+                }
+
+                let mut divergence = horizontal_speeds[right] - horizontal_speeds[(i, j)]
+                    + vertical_values[top]
+                    - vertical_values[(i, j)];
+                let compression = self.particle_density[(i, j)] - self.rest_density;
+                if self.rest_density > T::ZERO && compression > T::ZERO {
+                    divergence -= stiffness * compression;
+                }
+
+                let correction = overlax * (-divergence / number_of_open);
+                horizontal_speeds[(i, j)] -= correction * left_open;
+                horizontal_speeds[right] += correction * right_open;
+                vertical_values[(i, j)] -= correction * bottom_open;
+                vertical_values[top] += correction * top_open;
+                pressure_grid[(i, j)] += pressure_constant * correction;
+            }
+        }
+    }
+
+    /// updates the velocities of the particles with a blend of the grid velocities (PIC)
+    /// and of their change during the step (FLIP)
+    pub(crate) fn transfer_to_particles(
+        &mut self,
+        horizontal_speeds: &Grid<T, L>,
+        vertical_values: &Grid<T, L>,
+        flip_ratio: T,
+        h: T,
+    ) {
+        let half = T::from_f32(0.5) * h;
+        let cell_types = &self.cell_types;
+        // a face only has a velocity if there is liquid or a wall on one of its sides
+        let is_valid = |(x, y): (usize, usize), (step_x, step_y): (usize, usize)| {
+            cell_types[(x, y)] != CellType::Air
+                || cell_types[(x - step_x, y - step_y)] != CellType::Air
+        };
+        let sample = |field: &Grid<T, L>,
+                      previous: &Grid<T, L>,
+                      position: (T, T),
+                      offset: (T, T),
+                      step: (usize, usize)| {
+            let (mut total, mut value, mut change) = (T::ZERO, T::ZERO, T::ZERO);
+            for (cell, weight) in stencil(field, position, offset, h) {
+                if is_valid(cell, step) {
+                    total += weight;
+                    value += weight * field[cell];
+                    change += weight * (field[cell] - previous[cell]);
+                }
+            }
+            (total > T::ZERO).then(|| (value / total, change / total))
+        };
+
+        let (previous_horizontal, previous_vertical) =
+            (&self.previous_horizontal, &self.previous_vertical);
+        self.particles.par_iter_mut().for_each(|particle| {
+            let components = [
+                (
+                    &mut particle.velocity.0,
+                    horizontal_speeds,
+                    previous_horizontal,
+                    (T::ZERO, half),
+                    (1, 0),
+                ),
+                (
+                    &mut particle.velocity.1,
+                    vertical_values,
+                    previous_vertical,
+                    (half, T::ZERO),
+                    (0, 1),
+                ),
+            ];
+            for (velocity, field, previous, offset, step) in components {
+                if let Some((pic, change)) =
+                    sample(field, previous, particle.position, offset, step)
+                {
+                    let flip = *velocity + change;
+                    *velocity = (T::ONE - flip_ratio) * pic + flip_ratio * flip;
+                }
+            }
+        });
+    }
+
+    /// writes the empty part of every cell in the smoke field, 0 where it is full of liquid,
+    /// so the liquid is measured and shown like the smoke
+    pub(crate) fn write_fraction(&self, smoke_grid: &mut Grid<T, L>) {
+        for (index, smoke) in smoke_grid.as_mut_slice().iter_mut().enumerate() {
+            let (x, y) = self.cell_types.position(index);
+            *smoke = if self.cell_types[(x, y)] == CellType::Solid {
+                T::ONE
+            } else {
+                T::ONE - self.fraction_at(x, y)
+            };
+        }
+    }
+}
+
+/// the fraction of a cell filled with liquid from its particle density, 0 before the rest
+/// density is known
+#[inline]
+fn fraction<T: Scalar>(density: T, rest_density: T) -> T {
+    if rest_density <= T::ZERO {
+        return T::ZERO;
+    }
+    (density / rest_density).min(T::ONE)
+}
+
+/// true for the blocks and the border of the tank
+#[inline]
+pub(crate) fn is_solid<L: Layout>(blocks: &Grid<bool, L>, x: usize, y: usize) -> bool {
+    let (width, height) = blocks.size();
+    blocks[(x, y)] || x == 0 || y == 0 || x == width - 1 || y == height - 1
+}
+
+/// returns the four values of a field around a position and their bilinear weights,
+/// `offset` is where the value of the cell (0, 0) is stored in meters
+pub(crate) fn stencil<V, L: Layout, T: Scalar>(
+    field: &Grid<V, L>,
+    (x, y): (T, T),
+    (offset_x, offset_y): (T, T),
+    h: T,
+) -> [((usize, usize), T); 4] {
+    let (width, height) = field.size();
+    let x = x.max(h).min(T::from_usize(width - 1) * h) - offset_x;
+    let y = y.max(h).min(T::from_usize(height - 1) * h) - offset_y;
+
+    let left = (x / h).floor().max(T::ZERO).to_usize().min(width - 2);
+    let bottom = (y / h).floor().max(T::ZERO).to_usize().min(height - 2);
+    let ratio_x = (x / h - T::from_usize(left)).max(T::ZERO).min(T::ONE);
+    let ratio_y = (y / h - T::from_usize(bottom)).max(T::ZERO).min(T::ONE);
+    let (rest_x, rest_y) = (T::ONE - ratio_x, T::ONE - ratio_y);
+
+    [
+        ((left, bottom), rest_x * rest_y),
+        ((left + 1, bottom), ratio_x * rest_y),
+        ((left, bottom + 1), rest_x * ratio_y),
+        ((left + 1, bottom + 1), ratio_x * ratio_y),
+    ]
+}
@@ -1,4 +1,33 @@
 mod config;
+mod diagnostics;
+mod forces;
+mod grid;
+#[cfg(feature = "simd")]
+mod kernels;
+mod lattice;
+mod liquid;
+mod probes;
+mod scalar;
+mod shallow_water;
+#[allow(clippy::module_inception)]
 mod simulator;
-pub use config::SimConfig;
-pub use simulator::FluidSim;
+mod solver;
+mod sph;
+mod statistics;
+mod streamlines;
+mod tracers;
+pub use config::{SimConfig, SimMode, STANDARD_GRAVITY};
+pub use diagnostics::StepDiagnostics;
+pub use forces::ObstacleForces;
+pub use grid::{ColumnMajor, Grid, GridLayout, Layout, Positions, RowMajor, Tiled};
+pub use lattice::LatticeBoltzmann;
+pub use liquid::{CellType, Liquid, LiquidParticle};
+pub use probes::{Probe, ProbeSample};
+pub use scalar::Scalar;
+pub use shallow_water::ShallowWater;
+pub use simulator::{FluidSim, HeatSource};
+pub use solver::{Shading, Solver};
+pub use sph::Sph;
+pub use statistics::{FieldStatistics, RunningStatistics};
+pub use streamlines::{Streamline, StreamlineSettings};
+pub use tracers::{Emitter, Integrator, Tracer, Tracers, WallBehavior};
@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+use crate::{FluidSim, Layout, Scalar};
+
+/// what a probe measured at the end of a step
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ProbeSample<T: Scalar = f32> {
+    /// the simulated time in seconds
+    pub time: T,
+
+    /// the velocity at the center of the cell in m/s
+    pub velocity: (T, T),
+
+    /// the pressure in Pa
+    pub pressure: T,
+
+    /// the value of the smoke field, 0 where the smoke is the thickest
+    pub smoke: T,
+}
+
+/// a cell of the grid where the fluid is measured after every step
+///
+/// the history is a ring buffer, the oldest samples are dropped once it is full
+#[derive(Clone, Debug)]
+pub struct Probe<T: Scalar = f32> {
+    x: usize,
+    y: usize,
+    capacity: usize,
+    history: VecDeque<ProbeSample<T>>,
+}
+
+impl<T: Scalar> Probe<T> {
+    /// creates a probe at `(x, y)` that keeps the last `capacity` samples
+    pub fn new(x: usize, y: usize, capacity: usize) -> Self {
+        Self {
+            x,
+            y,
+            capacity,
+            history: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    #[inline]
+    pub fn position(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// the samples from the oldest to the latest
+    #[inline]
+    pub fn history(&self) -> &VecDeque<ProbeSample<T>> {
+        &self.history
+    }
+
+    #[inline]
+    pub fn latest(&self) -> Option<&ProbeSample<T>> {
+        self.history.back()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// returns the dominant frequency of the vertical velocity in Hz by counting
+    /// how many times it crosses its mean upwards, like the vortices shed behind an obstacle
+    ///
+    /// the velocity has to go below the mean by a tenth of its standard deviation
+    /// between two crossings so the noise does not count as crossings,
+    /// `None` until the history holds two full periods
+    pub fn vertical_frequency(&self) -> Option<T> {
+        if self.history.len() < 3 {
+            return None;
+        }
+        let count = T::from_usize(self.history.len());
+        let mean = self.history.iter().map(|s| s.velocity.1).sum::<T>() / count;
+        let variance = self
+            .history
+            .iter()
+            .map(|s| (s.velocity.1 - mean) * (s.velocity.1 - mean))
+            .sum::<T>()
+            / count;
+        let band = T::from_f32(0.1) * variance.sqrt();
+
+        let mut crossing_times = Vec::new();
+        let mut is_below = false;
+        for (before, after) in self.history.iter().zip(self.history.iter().skip(1)) {
+            let (before_value, after_value) = (before.velocity.1 - mean, after.velocity.1 - mean);
+            if before_value < -band {
+                is_below = true;
+            }
+            if is_below && before_value < T::ZERO && after_value >= T::ZERO {
+                // the time the velocity reached the mean between the two samples
+                let ratio = -before_value / (after_value - before_value);
+                crossing_times.push(before.time + (after.time - before.time) * ratio);
+                is_below = false;
+            }
+        }
+
+        let (first, last) = (crossing_times.first()?, crossing_times.last()?);
+        let periods = crossing_times.len() - 1;
+        if periods < 2 || *last <= *first {
+            return None;
+        }
+        Some(T::from_usize(periods) / (*last - *first))
+    }
+
+    pub(crate) fn record(&mut self, sample: ProbeSample<T>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(sample);
+    }
+}
+
+impl<T: Scalar, L: Layout> FluidSim<T, L> {
+    /// writes the history of every probe as csv, one line per sample
+    pub fn write_probes_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "probe,x,y,time,horizontal_speed,vertical_speed,pressure,smoke"
+        )?;
+        for (index, probe) in self.get_probes().iter().enumerate() {
+            let (x, y) = probe.position();
+            for sample in probe.history() {
+                let (u, v) = sample.velocity;
+                writeln!(
+                    writer,
+                    "{index},{x},{y},{},{u},{v},{},{}",
+                    sample.time, sample.pressure, sample.smoke
+                )?;
+            }
+        }
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a probe holding `count` samples of a sine of `frequency` Hz taken every `delta` seconds
+    fn sine_probe(frequency: f64, delta: f64, count: usize) -> Probe<f64> {
+        let mut probe = Probe::new(0, 0, count);
+        for index in 0..count {
+            let time = index as f64 * delta;
+            probe.record(ProbeSample {
+                time,
+                velocity: (0.0, 0.3 * (std::f64::consts::TAU * frequency * time).sin()),
+                ..ProbeSample::default()
+            });
+        }
+        probe
+    }
+
+    #[test]
+    fn a_sine_gives_back_its_frequency() {
+        let frequency = sine_probe(2.5, 0.01, 500).vertical_frequency().unwrap();
+        assert!((frequency - 2.5).abs() < 1e-3, "measured {frequency} Hz");
+    }
+
+    #[test]
+    fn less_than_two_periods_have_no_frequency() {
+        assert_eq!(sine_probe(2.5, 0.01, 60).vertical_frequency(), None);
+    }
+
+    #[test]
+    fn the_oldest_samples_are_dropped_when_full() {
+        let probe = sine_probe(1.0, 0.1, 10);
+        let mut full = Probe::new(0, 0, 4);
+        for sample in probe.history() {
+            full.record(*sample);
+        }
+        assert_eq!(full.history().len(), 4);
+        assert_eq!(full.history().front(), probe.history().get(6));
+    }
+}
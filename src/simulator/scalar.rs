@@ -0,0 +1,115 @@
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// the floating point type the simulator computes with
+///
+/// implemented for `f32`, which is fast enough for real time rendering,
+/// and `f64` for runs that need to be compared against reference data
+pub trait Scalar:
+    Copy
+    + Default
+    + Debug
+    + Display
+    + PartialOrd
+    + Send
+    + Sync
+    + Sum
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + 'static
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const INFINITY: Self;
+
+    fn from_f32(value: f32) -> Self;
+    fn from_f64(value: f64) -> Self;
+    fn from_usize(value: usize) -> Self;
+    fn to_f32(self) -> f32;
+
+    /// truncates the value towards zero, negative values become 0
+    fn to_usize(self) -> usize;
+
+    fn floor(self) -> Self;
+    fn trunc(self) -> Self;
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+}
+
+macro_rules! impl_scalar {
+    ($float:ty) => {
+        impl Scalar for $float {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const INFINITY: Self = <$float>::INFINITY;
+
+            #[inline]
+            fn from_f32(value: f32) -> Self {
+                value as $float
+            }
+
+            #[inline]
+            fn from_f64(value: f64) -> Self {
+                value as $float
+            }
+
+            #[inline]
+            fn from_usize(value: usize) -> Self {
+                value as $float
+            }
+
+            #[inline]
+            fn to_f32(self) -> f32 {
+                self as f32
+            }
+
+            #[inline]
+            fn to_usize(self) -> usize {
+                self as usize
+            }
+
+            #[inline]
+            fn floor(self) -> Self {
+                <$float>::floor(self)
+            }
+
+            #[inline]
+            fn trunc(self) -> Self {
+                <$float>::trunc(self)
+            }
+
+            #[inline]
+            fn abs(self) -> Self {
+                <$float>::abs(self)
+            }
+
+            #[inline]
+            fn sqrt(self) -> Self {
+                <$float>::sqrt(self)
+            }
+
+            #[inline]
+            fn min(self, other: Self) -> Self {
+                <$float>::min(self, other)
+            }
+
+            #[inline]
+            fn max(self, other: Self) -> Self {
+                <$float>::max(self, other)
+            }
+        }
+    };
+}
+
+impl_scalar!(f32);
+impl_scalar!(f64);
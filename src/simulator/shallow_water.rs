@@ -0,0 +1,432 @@
+use rayon::prelude::*;
+use std::time::Instant;
+
+use super::{
+    diagnostics::{cell_diagnostics, cell_velocity_at},
+    forces::rotate_faces,
+};
+use crate::{ColumnMajor, Grid, Layout, Scalar, Shading, SimConfig, Solver, StepDiagnostics};
+
+/// the depth of the water at rest in meters
+const REST_DEPTH: f32 = 0.05;
+
+/// how fast the bed slows the water down in 1/s
+const BED_FRICTION: f32 = 0.2;
+
+/// the most substeps taken in one step, a slower frame drops the time that does not fit
+const MAX_SUBSTEPS: usize = 32;
+
+/// a height field of water solving the shallow water equations
+///
+/// the depth is stored at the center of the cells and the depth averaged velocities on their faces,
+/// like the staggered grid of [`FluidSim`](crate::FluidSim). the blocks are walls the water
+/// flows around and the border of the domain is closed
+pub struct ShallowWater<T: Scalar = f32, L: Layout = ColumnMajor> {
+    /// the depth of the water in meters
+    depth: Grid<T, L>,
+
+    /// the horizontal speeds in m/s, stored on the left face of every cell
+    horizontal_speeds: Grid<T, L>,
+
+    /// the vertical speeds in m/s, stored on the bottom face of every cell
+    vertical_values: Grid<T, L>,
+
+    /// the velocity in m/s at the center of every cell, the mean of the speeds on its faces
+    velocity_grid: Grid<(T, T), L>,
+
+    /// the depth is updated into it before being swapped
+    depth_buffer: Grid<T, L>,
+
+    /// the hydrostatic pressure on the bed in Pa
+    pressure_grid: Grid<T, L>,
+
+    /// 1 where the cells are dry and 0 where the water is twice as deep as at rest
+    smoke_grid: Grid<T, L>,
+    block_grid: Grid<bool, L>,
+
+    width: usize,
+    height: usize,
+
+    // the last time the water has been calculated
+    last_instant: Instant,
+
+    config: SimConfig,
+}
+
+impl<T: Scalar, L: Layout> Default for ShallowWater<T, L> {
+    fn default() -> Self {
+        ShallowWater::new(2, 2, SimConfig::default())
+    }
+}
+
+impl<T: Scalar, L: Layout> ShallowWater<T, L> {
+    pub fn new(width: usize, height: usize, mut config: SimConfig) -> Self {
+        config.fit_domain(width);
+        let zeros = Grid::new(width, height, T::ZERO);
+        let mut water = Self {
+            depth: zeros.clone(),
+            horizontal_speeds: zeros.clone(),
+            vertical_values: zeros.clone(),
+            velocity_grid: Grid::new(width, height, (T::ZERO, T::ZERO)),
+            depth_buffer: zeros.clone(),
+            pressure_grid: zeros.clone(),
+            smoke_grid: zeros,
+            block_grid: Grid::new(width, height, false),
+            width,
+            height,
+            last_instant: Instant::now(),
+            config,
+        };
+        water.fill_water();
+        water
+    }
+
+    /// the depth of the water at the center of every cell in meters
+    #[inline]
+    pub fn get_depth_grid(&self) -> &Grid<T, L> {
+        &self.depth
+    }
+
+    /// the water is at rest with the left fifth of the domain twice as deep, like a dam about to break
+    fn fill_water(&mut self) {
+        let rest_depth = T::from_f32(REST_DEPTH);
+        let dam_width = self.width / 5;
+        for (x, y) in self.block_grid.positions() {
+            self.depth[(x, y)] = if self.block_grid[(x, y)] {
+                T::ZERO
+            } else if x < dam_width {
+                rest_depth + rest_depth
+            } else {
+                rest_depth
+            };
+        }
+        self.horizontal_speeds.fill(T::ZERO);
+        self.vertical_values.fill(T::ZERO);
+        self.update_fields();
+    }
+
+    /// the domain is seen from above so the whole gravity presses the water on its bed
+    fn gravity(&self) -> T {
+        let (x, y) = self.config.water_gravity();
+        T::from_f32(x.hypot(y))
+    }
+
+    /// the longest substep that keeps the fastest wave under half a cell per substep
+    fn max_substep(&self) -> T {
+        let max_depth = self.depth.iter().copied().fold(T::ZERO, T::max);
+        let max_speed = self
+            .horizontal_speeds
+            .iter()
+            .chain(self.vertical_values.iter())
+            .fold(T::ZERO, |max, speed| max.max(speed.abs()));
+        let wave_speed = (self.gravity() * max_depth).sqrt() + max_speed;
+        if wave_speed <= T::ZERO {
+            return T::INFINITY;
+        }
+        T::from_f32(0.5 * self.config.cell_size) / wave_speed
+    }
+
+    fn substep(&mut self, delta: T) {
+        self.accelerate(delta);
+        self.rotate(delta);
+        self.move_water(delta);
+    }
+
+    /// the water flows down the slope of its surface and is slowed down by the bed
+    fn accelerate(&mut self, delta: T) {
+        let h = T::from_f32(self.config.cell_size);
+        let acceleration = self.gravity() * delta / h;
+        let friction = (T::ONE - T::from_f32(BED_FRICTION) * delta).max(T::ZERO);
+        let (depth, blocks) = (&self.depth, &self.block_grid);
+
+        let components = [
+            (&mut self.horizontal_speeds, (1, 0)),
+            (&mut self.vertical_values, (0, 1)),
+        ];
+        for (speeds, (step_x, step_y)) in components {
+            speeds
+                .as_mut_slice()
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(index, speed)| {
+                    let (x, y) = blocks.position(index);
+                    // the faces on the border and on the blocks are closed
+                    if x < step_x || y < step_y {
+                        *speed = T::ZERO;
+                        return;
+                    }
+                    let before = (x - step_x, y - step_y);
+                    if blocks[(x, y)] || blocks[before] {
+                        *speed = T::ZERO;
+                        return;
+                    }
+                    *speed -= acceleration * (depth[(x, y)] - depth[before]);
+                    *speed *= friction;
+                });
+        }
+    }
+
+    /// adds the Coriolis and centrifugal forces of the rotating frame to the open faces
+    /// with water on one of their sides, nothing pushes the dry ones
+    fn rotate(&mut self, delta: T) {
+        let (width, height) = (self.width, self.height);
+        let (blocks, depth) = (&self.block_grid, &self.depth);
+        let is_wet = |x: usize, y: usize, (step_x, step_y): (usize, usize)| {
+            if x < step_x || y < step_y || x + 1 >= width || y + 1 >= height {
+                return false;
+            }
+            let before = (x - step_x, y - step_y);
+            !blocks[(x, y)]
+                && !blocks[before]
+                && (depth[(x, y)] > T::ZERO || depth[before] > T::ZERO)
+        };
+        rotate_faces(
+            &self.config,
+            &mut self.horizontal_speeds,
+            &mut self.vertical_values,
+            is_wet,
+            delta,
+        );
+    }
+
+    /// moves the water through the faces, the depth carried is the one upwind of the face
+    fn move_water(&mut self, delta: T) {
+        let ratio = delta / T::from_f32(self.config.cell_size);
+        let (width, height) = (self.width, self.height);
+        let (depth, u, v) = (&self.depth, &self.horizontal_speeds, &self.vertical_values);
+
+        // the flux going through the left or bottom face of a cell, 0 outside of the domain
+        let flux =
+            |speeds: &Grid<T, L>, (x, y): (usize, usize), (step_x, step_y): (usize, usize)| {
+                if x < step_x || y < step_y || x >= width || y >= height {
+                    return T::ZERO;
+                }
+                let speed = speeds[(x, y)];
+                let upwind = if speed > T::ZERO {
+                    depth[(x - step_x, y - step_y)]
+                } else {
+                    depth[(x, y)]
+                };
+                speed * upwind
+            };
+
+        self.depth_buffer
+            .as_mut_slice()
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, new_depth)| {
+                let (x, y) = depth.position(index);
+                let outflow = flux(u, (x + 1, y), (1, 0)) - flux(u, (x, y), (1, 0))
+                    + flux(v, (x, y + 1), (0, 1))
+                    - flux(v, (x, y), (0, 1));
+                *new_depth = (depth[(x, y)] - ratio * outflow).max(T::ZERO);
+            });
+        std::mem::swap(&mut self.depth, &mut self.depth_buffer);
+    }
+
+    /// fills the pressure and smoke fields from the depth and the velocities at the centers
+    /// of the cells from their faces, the faces on the right and top border are closed
+    fn update_fields(&mut self) {
+        let density_gravity = T::from_f32(self.config.density) * self.gravity();
+        let full_depth = T::from_f32(2.0 * REST_DEPTH);
+        let half = T::from_f32(0.5);
+        for (index, depth) in self.depth.iter().enumerate() {
+            self.pressure_grid.as_mut_slice()[index] = density_gravity * *depth;
+            self.smoke_grid.as_mut_slice()[index] = T::ONE - (*depth / full_depth).min(T::ONE);
+
+            let (x, y) = self.depth.position(index);
+            let right = if x + 1 < self.width {
+                self.horizontal_speeds[(x + 1, y)]
+            } else {
+                T::ZERO
+            };
+            let top = if y + 1 < self.height {
+                self.vertical_values[(x, y + 1)]
+            } else {
+                T::ZERO
+            };
+            self.velocity_grid[(x, y)] = (
+                (self.horizontal_speeds[(x, y)] + right) * half,
+                (self.vertical_values[(x, y)] + top) * half,
+            );
+        }
+    }
+
+    /// computes the diagnostics of the water, its volume is in m³ and its energy in J
+    pub fn diagnostics(&self) -> StepDiagnostics<T> {
+        cell_diagnostics(&self.velocity_grid, &self.config, |x, y| {
+            let depth = self.depth[(x, y)];
+            (!self.block_grid[(x, y)]).then_some((depth, depth))
+        })
+    }
+}
+
+impl<T: Scalar, L: Layout> Solver<T, L> for ShallowWater<T, L> {
+    fn name(&self) -> &'static str {
+        "Shallow Water"
+    }
+
+    fn next_step(&mut self) -> StepDiagnostics<T> {
+        let delta = T::from_f64(self.last_instant.elapsed().as_secs_f64());
+        let max_substep = self.max_substep();
+        let substeps = ((delta / max_substep).floor().to_usize() + 1).min(MAX_SUBSTEPS);
+        let substep = (delta / T::from_usize(substeps)).min(max_substep);
+        for _ in 0..substeps {
+            self.substep(substep);
+        }
+        self.update_fields();
+        self.last_instant = Instant::now();
+        self.diagnostics()
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.config.fit_domain(width);
+        let zeros = Grid::new(width, height, T::ZERO);
+        self.depth = zeros.clone();
+        self.horizontal_speeds = zeros.clone();
+        self.vertical_values = zeros.clone();
+        self.velocity_grid = Grid::new(width, height, (T::ZERO, T::ZERO));
+        self.depth_buffer = zeros.clone();
+        self.pressure_grid = zeros.clone();
+        self.smoke_grid = zeros;
+        self.block_grid.resize(width, height, false);
+        self.width = width;
+        self.height = height;
+        self.last_instant = Instant::now();
+        self.fill_water();
+    }
+
+    fn restart_sim(&mut self) {
+        self.resize(self.width, self.height);
+    }
+
+    fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn get_config(&self) -> &SimConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, mut config: SimConfig) {
+        config.fit_domain(self.width);
+        self.config = config;
+    }
+
+    fn get_block_grid(&self) -> &Grid<bool, L> {
+        &self.block_grid
+    }
+
+    fn set_block(&mut self, x: usize, y: usize) {
+        if let Some(cell) = self.block_grid.get_mut(x, y) {
+            *cell = true;
+        }
+    }
+
+    fn unset_block(&mut self, x: usize, y: usize) {
+        if let Some(cell) = self.block_grid.get_mut(x, y) {
+            *cell = false;
+        }
+    }
+
+    fn get_pressure_grid(&self) -> &Grid<T, L> {
+        &self.pressure_grid
+    }
+
+    fn get_smoke_grid(&self) -> &Grid<T, L> {
+        &self.smoke_grid
+    }
+
+    fn velocity_at(&self, x: T, y: T) -> (T, T) {
+        cell_velocity_at(
+            &self.velocity_grid,
+            (x, y),
+            T::from_f32(self.config.cell_size),
+        )
+    }
+
+    fn shading(&self) -> Shading {
+        Shading::Height
+    }
+
+    /// raises the water in a small bump around the cell, which spreads as a ring of waves
+    fn add_droplet(&mut self, x: usize, y: usize) {
+        const RADIUS: isize = 3;
+        let rest_depth = T::from_f32(REST_DEPTH);
+        for offset_x in -RADIUS..=RADIUS {
+            for offset_y in -RADIUS..=RADIUS {
+                let cell = (
+                    x.wrapping_add_signed(offset_x),
+                    y.wrapping_add_signed(offset_y),
+                );
+                let squared_distance = (offset_x * offset_x + offset_y * offset_y) as f32;
+                let is_water = self.block_grid.get(cell.0, cell.1) == Some(&false);
+                if !is_water || squared_distance > (RADIUS * RADIUS) as f32 {
+                    continue;
+                }
+                let bump = (-squared_distance / RADIUS as f32).exp();
+                self.depth[cell] += rest_depth * T::from_f32(bump);
+            }
+        }
+        self.update_fields();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// water around a wall of blocks in the middle of the domain, with the dam of [`ShallowWater::new`]
+    fn water_around_wall() -> ShallowWater<f64> {
+        let mut water = ShallowWater::new(40, 20, SimConfig::default());
+        for y in 5..15 {
+            water.set_block(20, y);
+        }
+        water.restart_sim();
+        water
+    }
+
+    fn run(water: &mut ShallowWater<f64>, substeps: usize) {
+        for _ in 0..substeps {
+            let substep = water.max_substep();
+            water.substep(substep);
+        }
+        water.update_fields();
+    }
+
+    #[test]
+    fn the_water_at_rest_stays_at_rest() {
+        let mut water = water_around_wall();
+        let rest_depth = f64::from(REST_DEPTH);
+        for (x, y) in water.block_grid.positions() {
+            water.depth[(x, y)] = if water.block_grid[(x, y)] {
+                0.0
+            } else {
+                rest_depth
+            };
+        }
+        water.update_fields();
+        let volume = water.diagnostics().smoke_mass;
+        run(&mut water, 50);
+
+        let diagnostics = water.diagnostics();
+        assert_eq!(diagnostics.max_speed, 0.0);
+        assert!((diagnostics.smoke_mass - volume).abs() < 1e-12 * volume);
+        assert!(water
+            .depth
+            .positions()
+            .all(|cell| water.block_grid[cell] || water.depth[cell] == rest_depth));
+    }
+
+    #[test]
+    fn the_breaking_dam_keeps_the_volume_of_water() {
+        let mut water = water_around_wall();
+        let volume = water.diagnostics().smoke_mass;
+        run(&mut water, 50);
+
+        let diagnostics = water.diagnostics();
+        assert!(diagnostics.max_speed > 0.0);
+        let lost = (diagnostics.smoke_mass - volume).abs() / volume;
+        assert!(lost < 1e-12, "{lost} of the water is lost");
+    }
+}
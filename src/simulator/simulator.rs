@@ -1,382 +1,1761 @@
 use rayon::prelude::*;
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
-use crate::SimConfig;
+use super::forces::rotate_faces;
+#[cfg(feature = "simd")]
+use super::kernels::{self, Advection, Projection, Samples};
+use crate::{
+    ColumnMajor, FieldStatistics, Grid, GridLayout, Layout, Liquid, Probe, ProbeSample, Scalar,
+    SimConfig, SimMode, StepDiagnostics, Tracers,
+};
+
+/// the over relaxation of the pressure solve, between 1 and 2
+const OVERLAX: f32 = 1.9;
+
+/// the number of iterations of the pressure solve
+const PROJECTION_ITERATIONS: usize = 50;
+
+/// the number of iterations of the pressure solve of the two phase mode, the hydrostatic pressure
+/// of the heavy fluid converges slowly and the volume of the phases drifts when it is not solved
+const TWO_PHASE_PROJECTION_ITERATIONS: usize = 100;
+
+/// the most substeps the liquid takes in one step
+const MAX_LIQUID_SUBSTEPS: usize = 8;
+
+/// the temperature above the ambient one in K of the fuel leaving the pipe, it burns
+/// as long as the ignition temperature is below it
+const PILOT_TEMPERATURE: f32 = 600.0;
+
+/// the temperature rise in K of a cell full of fuel that burns completely
+const HEAT_OF_COMBUSTION: f32 = 1800.0;
+
+/// the smoke made by a cell full of fuel that burns completely, 1 fills the cell
+const SOOT_PER_FUEL: f32 = 0.6;
+
+/// the part of the difference with the temperature of a block the fluid next to it
+/// takes every second through every face they share, in 1/s
+const WALL_HEAT_TRANSFER: f32 = 5.0;
+
+/// a cell holding the fluid in it at a fixed temperature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeatSource {
+    /// holds the temperature of the heaters of the config above the ambient one
+    Heater,
+
+    /// holds the temperature of the heaters of the config below the ambient one
+    Cooler,
+}
+
+/// the fields of the fire mode, only allocated in it
+struct FireFields<T: Scalar, L: Layout> {
+    /// 1 where a cell is full of fuel
+    fuel: Grid<T, L>,
+
+    /// the fuel is advected into it before being swapped
+    fuel_buffer: Grid<T, L>,
+}
+
+/// the temperature of the fluid, only allocated in the fire mode
+/// and in the smoke mode with heat sources or walls holding a temperature
+struct HeatFields<T: Scalar, L: Layout> {
+    /// the temperature above the ambient one in K
+    temperature: Grid<T, L>,
+
+    /// the temperature is advected into it before being swapped
+    temperature_buffer: Grid<T, L>,
+}
+
+/// the fields of the two phase mode, only allocated in it
+struct TwoPhaseFields<T: Scalar, L: Layout> {
+    /// the volume fraction of the second fluid, 1 where a cell is full of it
+    phases: Grid<T, L>,
+
+    /// the phases are advected into it before being swapped
+    phase_buffer: Grid<T, L>,
+
+    /// the inverse of the density of the left and bottom faces of every cell compared to
+    /// the first fluid, 0 for the walls
+    horizontal_face_weights: Grid<T, L>,
+    vertical_face_weights: Grid<T, L>,
+}
+
+/// an eulerian fluid simulation on a staggered grid
+///
+/// the simulation computes with `f32` by default, use `FluidSim<f64>` for double precision.
+/// the fields are stored column by column unless another [`Layout`] is given,
+/// the vectorized kernels of the `simd` feature are only used with the column major layout
+pub struct FluidSim<T: Scalar = f32, L: Layout = ColumnMajor> {
+    horizontal_speeds: Grid<T, L>,
+    vertical_values: Grid<T, L>,
+    pressure_grid: Grid<T, L>,
+    smoke_grid: Grid<T, L>,
+    block_grid: Grid<bool, L>,
+
+    /// the fuel of the fire mode, only in the fire mode
+    fire: Option<FireFields<T, L>>,
+
+    /// the temperature, only when something heats the fluid
+    heat: Option<HeatFields<T, L>>,
+
+    /// the heaters and coolers painted in the editor, they are kept when the sim restarts
+    heat_sources: Grid<Option<HeatSource>, L>,
+
+    /// the number of cells holding a heater or a cooler, so the heat is known without scanning them
+    heat_source_count: usize,
+
+    /// the phases and the face densities, only in the two phase mode
+    two_phase: Option<TwoPhaseFields<T, L>>,
+
+    /// back buffers the advection writes into before being swapped with the fields
+    horizontal_buffer: Grid<T, L>,
+    vertical_buffer: Grid<T, L>,
+    smoke_buffer: Grid<T, L>,
+
+    #[cfg(feature = "simd")]
+    projection: Projection<T>,
+    #[cfg(feature = "simd")]
+    advection: Advection<T>,
+
+    /// false to run the scalar loops instead of the kernels of the `simd` feature
+    #[cfg(feature = "simd")]
+    vectorized: bool,
+
+    /// the cells measured after every step
+    probes: Vec<Probe<T>>,
+
+    /// the running statistics of the fields, only accumulated when enabled
+    statistics: Option<FieldStatistics<T, L>>,
+
+    /// particles moved by the velocities after every step
+    tracers: Tracers<T>,
+
+    /// the particles carrying the liquid, only in the liquid mode
+    liquid: Option<Liquid<T, L>>,
 
-pub struct FluidSim {
-    /// all the values are indexed by x * height + y
-    horizontal_speeds: Vec<f32>,
-    vertical_values: Vec<f32>,
-    pressure_grid: Vec<f32>,
-    smoke_grid: Vec<f32>,
-    block_grid: Vec<bool>,
     width: usize,
     height: usize,
 
     // the last time the smoke has been calculated
     last_instant: Instant,
 
+    /// the simulated time in seconds since the last restart
+    time: T,
+
     config: SimConfig,
 }
 
-impl Default for FluidSim {
+impl<T: Scalar, L: Layout> Default for FluidSim<T, L> {
     fn default() -> Self {
         FluidSim::new(2, 2, SimConfig::default())
     }
 }
 
-impl FluidSim {
-    pub fn new(width: usize, height: usize, config: SimConfig) -> Self {
-        Self {
-            horizontal_speeds: Self::create_horizontal_speed(width, height, config.wind_speed),
-            vertical_values: vec![0.0; height * width],
-            pressure_grid: vec![0.0; height * width],
-            smoke_grid: Self::create_smoke_pipe(width, height, config.smoke_size),
-            block_grid: Self::make_block_grid(width, height),
+impl<T: Scalar, L: Layout> FluidSim<T, L> {
+    pub fn new(width: usize, height: usize, mut config: SimConfig) -> Self {
+        config.fit_domain(width);
+        let zeros = Grid::new(width, height, T::ZERO);
+        let block_grid = Self::make_block_grid(Grid::new(width, height, false));
+        let mut sim = Self {
+            horizontal_speeds: Self::create_horizontal_speed(
+                zeros.clone(),
+                Self::inflow_speed(&config),
+            ),
+            vertical_values: zeros.clone(),
+            pressure_grid: zeros.clone(),
+            smoke_grid: Self::create_smoke_pipe(zeros.clone(), Self::smoke_pipe_size(&config)),
+            fire: Self::create_fire(&zeros, &config),
+            heat: None,
+            two_phase: Self::create_two_phase(&zeros, &config),
+            heat_sources: Grid::new(width, height, None),
+            heat_source_count: 0,
+            liquid: Self::create_liquid(&block_grid, &config),
+            block_grid,
+            horizontal_buffer: zeros.clone(),
+            vertical_buffer: zeros.clone(),
+            smoke_buffer: zeros,
+            #[cfg(feature = "simd")]
+            projection: Projection::default(),
+            #[cfg(feature = "simd")]
+            advection: Advection::default(),
+            #[cfg(feature = "simd")]
+            vectorized: true,
+            probes: Vec::new(),
+            statistics: None,
+            tracers: Tracers::default(),
             width,
             height,
             config,
             last_instant: Instant::now(),
-        }
+            time: T::ZERO,
+        };
+        sim.update_heat();
+        sim
     }
 
     pub fn resize(&mut self, width: usize, height: usize) {
+        self.config.fit_domain(width);
+        let zeros = Grid::new(width, height, T::ZERO);
         self.horizontal_speeds =
-            Self::create_horizontal_speed(width, height, self.config.wind_speed);
-        self.vertical_values = vec![0.0; height * width];
-        self.pressure_grid = vec![0.0; height * width];
-        self.smoke_grid = Self::create_smoke_pipe(width, height, self.config.smoke_size);
+            Self::create_horizontal_speed(zeros.clone(), Self::inflow_speed(&self.config));
+        self.vertical_values = zeros.clone();
+        self.pressure_grid = zeros.clone();
+        self.smoke_grid =
+            Self::create_smoke_pipe(zeros.clone(), Self::smoke_pipe_size(&self.config));
+        self.fire = Self::create_fire(&zeros, &self.config);
+        self.two_phase = Self::create_two_phase(&zeros, &self.config);
         self.resize_block_grid(width, height);
+        self.heat_sources.resize(width, height, None);
+        self.heat_source_count = self
+            .heat_sources
+            .iter()
+            .filter(|source| source.is_some())
+            .count();
+        self.liquid = Self::create_liquid(&self.block_grid, &self.config);
+        self.horizontal_buffer.resize(width, height, T::ZERO);
+        self.vertical_buffer.resize(width, height, T::ZERO);
+        self.smoke_buffer.resize(width, height, T::ZERO);
         self.width = width;
         self.height = height;
         self.last_instant = Instant::now();
+        self.time = T::ZERO;
+        // the temperature starts over with the pilot flame
+        self.heat = None;
+        self.update_heat();
+
+        self.probes.retain(|probe| {
+            let (x, y) = probe.position();
+            x < width && y < height
+        });
+        self.probes.iter_mut().for_each(Probe::clear_history);
+        self.reset_statistics();
+
+        let (domain_width, domain_height) = self.get_domain_size();
+        self.tracers
+            .restart((T::from_f32(domain_width), T::from_f32(domain_height)));
     }
 
     pub fn restart_sim(&mut self) {
         self.resize(self.width, self.height);
     }
 
-    pub fn next_step(&mut self) {
-        let delta = self.last_instant.elapsed();
-        self.add_gravity(delta);
-        self.make_incompressible(delta);
-        self.move_velocity(delta);
+    /// runs the scalar loops instead of the vectorized kernels when `vectorized` is false,
+    /// to compare both in one build. the red black projection of the kernels does not
+    /// update the cells in the same order so both give slightly different fields
+    #[cfg(feature = "simd")]
+    pub fn set_vectorized(&mut self, vectorized: bool) {
+        self.vectorized = vectorized;
+    }
+
+    /// true if the steps run the kernels of the `simd` feature, which need the column major layout
+    #[cfg(feature = "simd")]
+    fn uses_kernels(&self) -> bool {
+        self.vectorized && L::LAYOUT == GridLayout::ColumnMajor
+    }
+
+    /// advances the simulation by the time elapsed since the last step
+    /// and returns the diagnostics of the new fields
+    pub fn next_step(&mut self) -> StepDiagnostics<T> {
+        self.step(T::from_f64(self.last_instant.elapsed().as_secs_f64()))
+    }
+
+    /// advances the simulation by `delta` seconds and returns the diagnostics of the new fields,
+    /// a fixed step makes the runs reproducible whatever the speed of the machine
+    pub fn step(&mut self, delta: T) -> StepDiagnostics<T> {
+        // the pressure is solved for a step of some length
+        if delta <= T::ZERO {
+            return self.diagnostics();
+        }
+        if self.liquid.is_some() {
+            self.step_liquid(delta);
+        } else {
+            match self.config.mode {
+                SimMode::TwoPhase => {
+                    self.update_face_weights();
+                    self.add_reduced_gravity(delta);
+                }
+                SimMode::Smoke | SimMode::Fire => {
+                    self.add_gravity(delta);
+                    if self.heat.is_some() {
+                        self.add_buoyancy(delta);
+                    }
+                }
+                SimMode::Liquid => self.add_gravity(delta),
+            }
+            self.add_rotation(delta);
+            self.diffuse_velocity(delta);
+            self.make_incompressible(delta);
+            self.move_velocity(delta);
+            match self.config.mode {
+                SimMode::Fire => self.burn_fuel(delta),
+                SimMode::TwoPhase => self.extend_phases_to_border(),
+                SimMode::Smoke | SimMode::Liquid => {}
+            }
+            if self.heat.is_some() {
+                self.apply_heat(delta);
+            }
+        }
+        self.move_tracers(delta);
         self.last_instant = Instant::now();
+        self.time += delta;
+        self.record_probes();
+        self.record_statistics();
+        self.diagnostics()
+    }
+
+    /// moves the particles of the liquid and solves the pressure in the cells they fill,
+    /// in substeps short enough that no particle moves more than a cell
+    fn step_liquid(&mut self, delta: T) {
+        let Some(mut liquid) = self.liquid.take() else {
+            return;
+        };
+        let h = T::from_f32(self.config.cell_size);
+        let flip_ratio = T::from_f32(self.config.flip_ratio.clamp(0.0, 1.0));
+
+        let config = &self.config;
+        let (gravity_x, gravity_y) = (T::from_f32(config.gravity.0), T::from_f32(config.gravity.1));
+        let domain_size = config.domain_size(self.width, self.height);
+        let center = (
+            T::from_f32(0.5 * domain_size.0),
+            T::from_f32(0.5 * domain_size.1),
+        );
+        let acceleration = |(x, y): (T, T), velocity: (T, T)| {
+            let (ax, ay) = config.frame_acceleration((x - center.0, y - center.1), velocity);
+            (gravity_x + ax, gravity_y + ay)
+        };
+        let max_acceleration = T::from_f32(config.max_body_acceleration(domain_size));
+
+        let travel = liquid.max_travel(max_acceleration, delta) / h;
+        let capillary = delta / Self::capillary_time_step(config);
+        let substeps = (travel.max(capillary).floor().to_usize() + 1).min(MAX_LIQUID_SUBSTEPS);
+        let delta = delta / T::from_usize(substeps);
+        let pressure_constant = T::from_f32(config.density) * h / delta;
+        let tension = T::from_f32(config.surface_tension / config.density);
+
+        for _ in 0..substeps {
+            liquid.move_particles(&self.block_grid, acceleration, delta, h);
+            liquid.transfer_to_grid(
+                &mut self.horizontal_speeds,
+                &mut self.vertical_values,
+                &self.block_grid,
+                h,
+            );
+            liquid.update_density(h);
+            liquid.add_surface_tension(
+                &mut self.horizontal_speeds,
+                &mut self.vertical_values,
+                tension,
+                delta,
+                h,
+            );
+            liquid.project(
+                &mut self.horizontal_speeds,
+                &mut self.vertical_values,
+                &mut self.pressure_grid,
+                pressure_constant,
+                PROJECTION_ITERATIONS,
+                T::from_f32(OVERLAX),
+            );
+            liquid.transfer_to_particles(
+                &self.horizontal_speeds,
+                &self.vertical_values,
+                flip_ratio,
+                h,
+            );
+        }
+        liquid.write_fraction(&mut self.smoke_grid);
+        self.liquid = Some(liquid);
+    }
+
+    /// the longest step that resolves the fastest capillary wave on the grid,
+    /// infinite without surface tension
+    fn capillary_time_step(config: &SimConfig) -> T {
+        if config.surface_tension <= 0.0 {
+            return T::INFINITY;
+        }
+        let h = config.cell_size;
+        T::from_f32(
+            (config.density * h * h * h / (2.0 * std::f32::consts::PI * config.surface_tension))
+                .sqrt(),
+        )
+    }
+
+    fn move_tracers(&mut self, delta: T) {
+        let mut tracers = std::mem::take(&mut self.tracers);
+        tracers.advance(self, delta);
+        self.tracers = tracers;
+    }
+
+    fn record_probes(&mut self) {
+        let mut probes = std::mem::take(&mut self.probes);
+        for probe in &mut probes {
+            let (x, y) = probe.position();
+            probe.record(ProbeSample {
+                time: self.time,
+                velocity: self.cell_velocity(x, y),
+                pressure: self.pressure_grid[(x, y)],
+                smoke: self.smoke_grid[(x, y)],
+            });
+        }
+        self.probes = probes;
+    }
+
+    fn record_statistics(&mut self) {
+        let Some(mut statistics) = self.statistics.take() else {
+            return;
+        };
+        let velocities = self
+            .block_grid
+            .positions()
+            .map(|(x, y)| self.cell_velocity(x, y));
+        statistics.add(velocities, &self.pressure_grid, &self.smoke_grid);
+        self.statistics = Some(statistics);
     }
 
-    pub fn set_config(&mut self, config: SimConfig) {
-        Self::set_horizontal_speed(&mut self.horizontal_speeds, self.height, config.wind_speed);
-        Self::set_smoke_pipe(&mut self.smoke_grid, self.height, config.smoke_size);
+    pub fn set_config(&mut self, mut config: SimConfig) {
+        config.fit_domain(self.width);
+        let mode_changed = config.mode != self.config.mode;
+        Self::set_horizontal_speed(&mut self.horizontal_speeds, Self::inflow_speed(&config));
+        Self::set_smoke_pipe(&mut self.smoke_grid, Self::smoke_pipe_size(&config));
+        if let Some(fire) = &mut self.fire {
+            Self::set_fuel_pipe(&mut fire.fuel, &config);
+        }
+        if let Some(heat) = &mut self.heat {
+            Self::set_pilot_flame(&mut heat.temperature, &config);
+        }
         self.config = config;
+        if mode_changed {
+            self.restart_sim();
+        } else {
+            self.update_heat();
+        }
+    }
+
+    fn create_liquid(block_grid: &Grid<bool, L>, config: &SimConfig) -> Option<Liquid<T, L>> {
+        (config.mode == SimMode::Liquid).then(|| Liquid::new(block_grid, config.cell_size))
+    }
+
+    /// the two phase mode is a closed tank without wind
+    fn inflow_speed(config: &SimConfig) -> f32 {
+        if config.mode == SimMode::TwoPhase {
+            0.0
+        } else {
+            config.wind_speed
+        }
     }
 
-    fn create_horizontal_speed(width: usize, height: usize, wind_speed: f32) -> Vec<f32> {
-        let mut values = vec![0.0; width * height];
-        Self::set_horizontal_speed(&mut values, height, wind_speed);
+    fn create_horizontal_speed(mut values: Grid<T, L>, wind_speed: f32) -> Grid<T, L> {
+        Self::set_horizontal_speed(&mut values, wind_speed);
         values
     }
 
-    fn set_horizontal_speed(horizontal_speeds: &mut [f32], height: usize, wind_speed: f32) {
+    fn set_horizontal_speed(horizontal_speeds: &mut Grid<T, L>, wind_speed: f32) {
         horizontal_speeds
-            .iter_mut()
-            .take(height * 2)
-            .skip(height)
-            .for_each(|speed| *speed = wind_speed);
+            .column_mut(1)
+            .for_each(|speed| *speed = T::from_f32(wind_speed));
     }
 
-    fn create_smoke_pipe(width: usize, height: usize, smoke_size: f32) -> Vec<f32> {
-        let mut values = vec![1.0; width * height];
-        Self::set_smoke_pipe(&mut values, height, smoke_size);
+    fn create_smoke_pipe(mut values: Grid<T, L>, smoke_size: f32) -> Grid<T, L> {
+        values.fill(T::ONE);
+        Self::set_smoke_pipe(&mut values, smoke_size);
 
         values
     }
 
-    fn set_smoke_pipe(smoke_grid: &mut [f32], height: usize, smoke_size: f32) {
-        // get rid of the previous smoke if any
-        smoke_grid
-            .iter_mut()
-            .take(height)
-            .for_each(|smoke_cell| *smoke_cell = 1.0);
+    pub(crate) fn set_smoke_pipe(smoke_grid: &mut Grid<T, L>, smoke_size: f32) {
+        Self::set_pipe(smoke_grid, smoke_size, T::ONE, T::ZERO);
+    }
+
+    /// the fire mode blows fuel out of the pipe and makes its smoke by burning it,
+    /// the closed tank of the two phase mode has no pipe
+    fn smoke_pipe_size(config: &SimConfig) -> f32 {
+        match config.mode {
+            SimMode::Fire | SimMode::TwoPhase => 0.0,
+            SimMode::Smoke | SimMode::Liquid => config.smoke_size,
+        }
+    }
+
+    fn create_fire(zeros: &Grid<T, L>, config: &SimConfig) -> Option<FireFields<T, L>> {
+        (config.mode == SimMode::Fire).then(|| {
+            let mut fuel = zeros.clone();
+            Self::set_fuel_pipe(&mut fuel, config);
+            FireFields {
+                fuel,
+                fuel_buffer: zeros.clone(),
+            }
+        })
+    }
+
+    fn set_fuel_pipe(fuel_grid: &mut Grid<T, L>, config: &SimConfig) {
+        let fuel_size = if config.mode == SimMode::Fire {
+            config.smoke_size
+        } else {
+            0.0
+        };
+        Self::set_pipe(fuel_grid, fuel_size, T::ZERO, T::ONE);
+    }
+
+    /// allocates the temperature once something heats the fluid and drops it when nothing does
+    fn update_heat(&mut self) {
+        match (self.is_heated(), self.heat.is_some()) {
+            (true, false) => {
+                let zeros = Grid::new(self.width, self.height, T::ZERO);
+                let mut temperature = zeros.clone();
+                Self::set_pilot_flame(&mut temperature, &self.config);
+                self.heat = Some(HeatFields {
+                    temperature,
+                    temperature_buffer: zeros,
+                });
+            }
+            (false, true) => self.heat = None,
+            _ => {}
+        }
+    }
+
+    /// the fuel leaves the pipe hot enough to catch fire
+    fn set_pilot_flame(temperature_grid: &mut Grid<T, L>, config: &SimConfig) {
+        let flame_size = if config.mode == SimMode::Fire {
+            config.smoke_size
+        } else {
+            0.0
+        };
+        Self::set_pipe(
+            temperature_grid,
+            flame_size,
+            T::ZERO,
+            T::from_f32(PILOT_TEMPERATURE),
+        );
+    }
 
-        let pipe_height = height as f32 * smoke_size;
+    /// fills the top half of the tank with the second fluid in the two phase mode,
+    /// the interface is a little wavy so the heavy fluid starts sinking in some places first
+    fn create_two_phase(zeros: &Grid<T, L>, config: &SimConfig) -> Option<TwoPhaseFields<T, L>> {
+        if config.mode != SimMode::TwoPhase {
+            return None;
+        }
+        let (width, height) = zeros.size();
+        let phases = Grid::from_fn(width, height, |x, y| {
+            let wave = (std::f32::consts::TAU * x as f32 / width as f32).cos();
+            let interface = height as f32 * 0.5 + 2.0 * wave;
+            if y as f32 + 0.5 > interface {
+                T::ONE
+            } else {
+                T::ZERO
+            }
+        });
+        Some(TwoPhaseFields {
+            phases,
+            phase_buffer: zeros.clone(),
+            horizontal_face_weights: zeros.clone(),
+            vertical_face_weights: zeros.clone(),
+        })
+    }
+
+    /// fills the left border with `outside`, except for a pipe in the middle filled with `inside`
+    /// that covers `size` of the height
+    fn set_pipe(grid: &mut Grid<T, L>, size: f32, outside: T, inside: T) {
+        // get rid of the previous pipe if any
+        grid.column_mut(0).for_each(|cell| *cell = outside);
+
+        let height = grid.height();
+        let pipe_height = height as f32 * size;
         let middle = height as f32 * 0.5;
         let min_index = (middle - pipe_height * 0.5) as usize;
         let max_index = (middle + pipe_height * 0.5) as usize;
 
-        smoke_grid
-            .iter_mut()
+        grid.column_mut(0)
             .take(max_index)
             .skip(min_index)
-            .for_each(|smoke_cell| *smoke_cell = 0.0);
+            .for_each(|cell| *cell = inside);
     }
 
-    fn make_block_grid(width: usize, height: usize) -> Vec<bool> {
-        let mut grid = vec![false; height * width];
-
+    fn make_block_grid(mut grid: Grid<bool, L>) -> Grid<bool, L> {
         // filling the left border
-        grid.iter_mut().take(height).for_each(|cell| *cell = true);
+        grid.column_mut(0).for_each(|cell| *cell = true);
         grid
     }
 
     fn resize_block_grid(&mut self, width: usize, height: usize) {
-        // remove the left border
-        self.block_grid
-            .iter_mut()
-            .take(self.height)
-            .for_each(|cell| *cell = false);
-
-        self.block_grid.resize(width * height, false);
+        self.block_grid.resize(width, height, false);
 
         // set the left border for the new height
-        self.block_grid
-            .iter_mut()
-            .take(height)
-            .for_each(|cell| *cell = true);
+        self.block_grid.column_mut(0).for_each(|cell| *cell = true);
     }
 
-    fn add_gravity(&mut self, delta: Duration) {
+    fn add_gravity(&mut self, delta: T) {
+        let (gravity_x, gravity) = (
+            T::from_f32(self.config.gravity.0) * delta,
+            T::from_f32(self.config.gravity.1) * delta,
+        );
+
+        // the horizontal faces next to the block of the left border hold the wind
+        if gravity_x != T::ZERO {
+            let blocks = &self.block_grid;
+            self.horizontal_speeds
+                .as_mut_slice()
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(index, horizontal_speed)| {
+                    let (i, j) = blocks.position(index);
+                    if Self::is_border_with_size(i, j, self.width, self.height)
+                        || blocks[(i, j)]
+                        || blocks[(i - 1, j)]
+                    {
+                        return;
+                    }
+                    *horizontal_speed += gravity_x;
+                });
+        }
+
+        #[cfg(feature = "simd")]
+        if self.uses_kernels() {
+            kernels::add_gravity(
+                self.vertical_values.as_mut_slice(),
+                self.block_grid.as_slice(),
+                self.height,
+                gravity,
+            );
+            return;
+        }
+
+        let blocks = &self.block_grid;
         self.vertical_values
+            .as_mut_slice()
             .par_iter_mut()
             .enumerate()
             .for_each(|(index, vertical_value)| {
-                if self.block_grid[index]
-                    || Self::index_is_border_with_size(index, self.width, self.height)
-                {
+                let (i, j) = blocks.position(index);
+                if blocks[(i, j)] || Self::is_border_with_size(i, j, self.width, self.height) {
                     return;
                 }
-                *vertical_value += self.config.gravity * delta.as_secs_f32();
+                *vertical_value += gravity;
             });
     }
 
-    fn make_incompressible(&mut self, delta: Duration) {
-        const OVERLAX: f32 = 1.9;
-        const ITERATIONS: usize = 50;
-        self.pressure_grid.fill(0.0);
-        let pressure_constant = self.config.density / delta.as_secs_f32();
+    /// pushes the hot gas against the gravity, or up without gravity,
+    /// the faces get the mean temperature of the cells around them
+    fn add_buoyancy(&mut self, delta: T) {
+        let (gravity_x, gravity_y) = self.config.gravity;
+        let up = match gravity_x.hypot(gravity_y) {
+            0.0 => (0.0, 1.0),
+            norm => (-gravity_x / norm, -gravity_y / norm),
+        };
+        let lift = T::from_f32(0.5 * self.config.buoyancy) * delta;
+        let (width, height) = (self.width, self.height);
+        let Some(heat) = &self.heat else {
+            return;
+        };
+        let (blocks, temperatures) = (&self.block_grid, &heat.temperature);
 
-        for _ in 0..ITERATIONS {
-            for i in 0..self.width {
-                for j in 0..self.height {
-                    let index = self.calculate_index(i, j);
-                    if self.block_grid[index] || self.index_is_border(index) {
-                        continue;
+        let faces = [
+            (&mut self.horizontal_speeds, up.0, (1, 0)),
+            (&mut self.vertical_values, up.1, (0, 1)),
+        ];
+        for (values, direction, (step_x, step_y)) in faces {
+            if direction == 0.0 {
+                continue;
+            }
+            let lift = lift * T::from_f32(direction);
+            values
+                .as_mut_slice()
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(index, value)| {
+                    let (i, j) = blocks.position(index);
+                    let before = (i.wrapping_sub(step_x), j.wrapping_sub(step_y));
+                    if Self::is_border_with_size(i, j, width, height)
+                        || blocks[(i, j)]
+                        || blocks[before]
+                    {
+                        return;
                     }
-                    let [top, right, bottom, left] = self.indexes_around(i, j);
-                    let top_is_block = !self.block_grid[top] as u8 as f32;
-                    let right_is_block = !self.block_grid[right] as u8 as f32;
-                    let bottom_is_block = !self.block_grid[bottom] as u8 as f32;
-                    let left_is_block = !self.block_grid[left] as u8 as f32;
-                    let number_of_fluids =
-                        right_is_block + top_is_block + left_is_block + bottom_is_block;
-
-                    if number_of_fluids == 0.0 {
-                        continue;
+                    *value += lift * (temperatures[(i, j)] + temperatures[before]);
+                });
+        }
+    }
+
+    /// burns the fuel of the cells hotter than the ignition temperature into heat and smoke,
+    /// then cools every cell towards the ambient temperature
+    fn burn_fuel(&mut self, delta: T) {
+        let ignition = T::from_f32(self.config.ignition_temperature);
+        let burned_part = (T::from_f32(self.config.burn_rate) * delta).min(T::ONE);
+        let cooling = (T::ONE - T::from_f32(self.config.cooling_rate) * delta).max(T::ZERO);
+        let (heat, soot) = (T::from_f32(HEAT_OF_COMBUSTION), T::from_f32(SOOT_PER_FUEL));
+        let (blocks, width, height) = (&self.block_grid, self.width, self.height);
+        let (Some(fire), Some(heat_fields)) = (&mut self.fire, &mut self.heat) else {
+            return;
+        };
+
+        (
+            fire.fuel.as_mut_slice(),
+            heat_fields.temperature.as_mut_slice(),
+            self.smoke_grid.as_mut_slice(),
+        )
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(index, (fuel, temperature, smoke))| {
+                let (i, j) = blocks.position(index);
+                if blocks[(i, j)] || Self::is_border_with_size(i, j, width, height) {
+                    return;
+                }
+                if *temperature > ignition {
+                    let burned = *fuel * burned_part;
+                    *fuel -= burned;
+                    *temperature += burned * heat;
+                    *smoke = (*smoke - burned * soot).max(T::ZERO);
+                }
+                *temperature *= cooling;
+            });
+    }
+
+    /// the fire mode always carries heat, the smoke mode only once something heats it
+    fn is_heated(&self) -> bool {
+        match self.config.mode {
+            SimMode::Fire => true,
+            SimMode::Smoke => self.config.wall_temperature.is_some() || self.heat_source_count > 0,
+            SimMode::Liquid | SimMode::TwoPhase => false,
+        }
+    }
+
+    /// holds the heaters and coolers at their temperature, the blocks at the temperature
+    /// of the walls and brings the fluid next to the blocks towards it
+    fn apply_heat(&mut self, delta: T) {
+        let source_temperature = T::from_f32(self.config.heater_temperature);
+        let wall_temperature = self.config.wall_temperature.map(T::from_f32);
+        let transfer = T::from_f32(WALL_HEAT_TRANSFER) * delta;
+        let (width, height) = (self.width, self.height);
+        let (blocks, sources) = (&self.block_grid, &self.heat_sources);
+        let Some(heat) = &mut self.heat else {
+            return;
+        };
+
+        heat.temperature
+            .as_mut_slice()
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, temperature)| {
+                let (i, j) = blocks.position(index);
+                // the border holds the inflow and the pilot flame
+                if Self::is_border_with_size(i, j, width, height) {
+                    return;
+                }
+                match (blocks[(i, j)], sources[(i, j)], wall_temperature) {
+                    (true, _, Some(wall_temperature)) => *temperature = wall_temperature,
+                    (true, _, None) => {}
+                    (false, Some(HeatSource::Heater), _) => *temperature = source_temperature,
+                    (false, Some(HeatSource::Cooler), _) => *temperature = -source_temperature,
+                    (false, None, Some(wall_temperature)) => {
+                        let faces = [(i, j + 1), (i + 1, j), (i, j - 1), (i - 1, j)]
+                            .into_iter()
+                            .filter(|&neighbor| blocks[neighbor])
+                            .count();
+                        let taken = (transfer * T::from_usize(faces)).min(T::ONE);
+                        *temperature += taken * (wall_temperature - *temperature);
                     }
+                    (false, None, None) => {}
+                }
+            });
+    }
+
+    /// explicit viscous diffusion of the velocities
+    fn diffuse_velocity(&mut self, delta: T) {
+        let h = T::from_f32(self.config.cell_size);
+        let viscosity = T::from_f32(self.config.viscosity);
+        // the explicit scheme is only stable below 0.25
+        let diffusion = (viscosity * delta / (h * h)).min(T::from_f32(0.25));
+        if diffusion <= T::ZERO {
+            return;
+        }
+
+        let (mut new_horizontal, mut new_vertical) = self.take_velocity_buffers();
+        let laplacian = |field: &Grid<T, L>, i: usize, j: usize| {
+            self.positions_around(i, j)
+                .into_iter()
+                .map(|neighbor| field[neighbor])
+                .sum::<T>()
+                - T::from_f32(4.0) * field[(i, j)]
+        };
+
+        (new_horizontal.as_mut_slice(), new_vertical.as_mut_slice())
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(index, (horizontal_value, vertical_value))| {
+                let (i, j) = self.block_grid.position(index);
+                if self.block_grid[(i, j)] || self.is_border(i, j) {
+                    return;
+                }
+                *horizontal_value += diffusion * laplacian(&self.horizontal_speeds, i, j);
+                *vertical_value += diffusion * laplacian(&self.vertical_values, i, j);
+            });
+        self.swap_velocity_buffers(new_horizontal, new_vertical);
+    }
+
+    fn make_incompressible(&mut self, delta: T) {
+        let overlax = T::from_f32(OVERLAX);
+        self.pressure_grid.fill(T::ZERO);
+        let pressure_constant =
+            T::from_f32(self.config.density) * T::from_f32(self.config.cell_size) / delta;
+
+        if self.config.mode == SimMode::TwoPhase {
+            self.project_two_phases(overlax, pressure_constant);
+            return;
+        }
+
+        #[cfg(feature = "simd")]
+        if self.uses_kernels() {
+            let (width, height) = (self.width, self.height);
+            self.projection
+                .prepare(self.block_grid.as_slice(), width, height, overlax);
+            self.projection.project(
+                self.horizontal_speeds.as_mut_slice(),
+                self.vertical_values.as_mut_slice(),
+                self.pressure_grid.as_mut_slice(),
+                pressure_constant,
+                PROJECTION_ITERATIONS,
+            );
+            return;
+        }
+
+        for _ in 0..PROJECTION_ITERATIONS {
+            // walking the cells in storage order keeps the iterations cache friendly
+            for (i, j) in self.block_grid.positions() {
+                if self.block_grid[(i, j)] || self.is_border(i, j) {
+                    continue;
+                }
+                let [top, right, bottom, left] = self.positions_around(i, j);
+                let top_is_block = Self::fluid_factor(self.block_grid[top]);
+                let right_is_block = Self::fluid_factor(self.block_grid[right]);
+                let bottom_is_block = Self::fluid_factor(self.block_grid[bottom]);
+                let left_is_block = Self::fluid_factor(self.block_grid[left]);
+                let number_of_fluids =
+                    right_is_block + top_is_block + left_is_block + bottom_is_block;
+
+                if number_of_fluids == T::ZERO {
+                    continue;
+                }
+
+                let divergence = self.horizontal_speeds[right] - self.horizontal_speeds[(i, j)]
+                    + self.vertical_values[top]
+                    - self.vertical_values[(i, j)];
+
+                let correction = overlax * (-divergence / number_of_fluids);
+                self.horizontal_speeds[(i, j)] -= correction * left_is_block;
+                self.horizontal_speeds[right] += correction * right_is_block;
 
-                    let divergence = self.horizontal_speeds[right] - self.horizontal_speeds[index]
-                        + self.vertical_values[top]
-                        - self.vertical_values[index];
+                self.vertical_values[(i, j)] -= correction * bottom_is_block;
+                self.vertical_values[top] += correction * top_is_block;
+                self.pressure_grid[(i, j)] += pressure_constant * correction;
+            }
+        }
+    }
+
+    /// the projection of the closed tank of the two phase mode, the pressure moves every face
+    /// by the inverse of its density so the heavy fluid is harder to push than the light one
+    fn project_two_phases(&mut self, overlax: T, pressure_constant: T) {
+        let Some(two_phase) = &self.two_phase else {
+            return;
+        };
+        // nothing goes through the walls
+        let faces = [
+            (
+                &mut self.horizontal_speeds,
+                &two_phase.horizontal_face_weights,
+            ),
+            (&mut self.vertical_values, &two_phase.vertical_face_weights),
+        ];
+        for (speeds, weights) in faces {
+            for (speed, &weight) in speeds.iter_mut().zip(weights.iter()) {
+                if weight == T::ZERO {
+                    *speed = T::ZERO;
+                }
+            }
+        }
 
-                    let correction = OVERLAX * (-divergence / number_of_fluids);
-                    self.horizontal_speeds[index] -= correction * left_is_block;
-                    self.horizontal_speeds[right] += correction * right_is_block;
+        let (horizontal_weights, vertical_weights) = (
+            &two_phase.horizontal_face_weights,
+            &two_phase.vertical_face_weights,
+        );
+        for _ in 0..TWO_PHASE_PROJECTION_ITERATIONS {
+            for (i, j) in self.block_grid.positions() {
+                if self.block_grid[(i, j)] || self.is_border(i, j) {
+                    continue;
+                }
+                let [top, right, _, _] = self.positions_around(i, j);
+                let left_weight = horizontal_weights[(i, j)];
+                let right_weight = horizontal_weights[right];
+                let bottom_weight = vertical_weights[(i, j)];
+                let top_weight = vertical_weights[top];
+                let total_weight = left_weight + right_weight + bottom_weight + top_weight;
 
-                    self.vertical_values[index] -= correction * bottom_is_block;
-                    self.vertical_values[top] += correction * top_is_block;
-                    self.pressure_grid[index] += pressure_constant * correction;
+                if total_weight == T::ZERO {
+                    continue;
                 }
+
+                let divergence = self.horizontal_speeds[right] - self.horizontal_speeds[(i, j)]
+                    + self.vertical_values[top]
+                    - self.vertical_values[(i, j)];
+
+                let correction = overlax * (-divergence / total_weight);
+                self.horizontal_speeds[(i, j)] -= correction * left_weight;
+                self.horizontal_speeds[right] += correction * right_weight;
+
+                self.vertical_values[(i, j)] -= correction * bottom_weight;
+                self.vertical_values[top] += correction * top_weight;
+                self.pressure_grid[(i, j)] += pressure_constant * correction;
             }
         }
     }
 
-    fn move_velocity(&mut self, delta: Duration) {
-        let mut new_horizontal = self.horizontal_speeds.clone();
-        let mut new_vertical = self.vertical_values.clone();
-        let mut new_smoke = self.smoke_grid.clone();
+    /// adds the gravity minus the part the hydrostatic pressure of the first fluid balances,
+    /// so only the faces heavier than the first fluid are pulled down and the pressure solve
+    /// does not have to build the hydrostatic pressure of the whole tank every step
+    fn add_reduced_gravity(&mut self, delta: T) {
+        let (gravity_x, gravity_y) = self.config.gravity;
+        let Some(two_phase) = &self.two_phase else {
+            return;
+        };
+        let faces = [
+            (
+                &mut self.horizontal_speeds,
+                &two_phase.horizontal_face_weights,
+                gravity_x,
+            ),
+            (
+                &mut self.vertical_values,
+                &two_phase.vertical_face_weights,
+                gravity_y,
+            ),
+        ];
+        for (values, weights, gravity) in faces {
+            let gravity = T::from_f32(gravity) * delta;
+            values
+                .as_mut_slice()
+                .par_iter_mut()
+                .zip(weights.as_slice().par_iter())
+                .for_each(|(value, &weight)| {
+                    if weight > T::ZERO {
+                        *value += gravity * (T::ONE - weight);
+                    }
+                });
+        }
+    }
+
+    /// adds the Coriolis and centrifugal forces of the rotating frame to the faces
+    /// between two fluid cells
+    fn add_rotation(&mut self, delta: T) {
+        let (width, height, blocks) = (self.width, self.height, &self.block_grid);
+        // the border has no cell before it
+        let is_fluid_face = |i: usize, j: usize, (step_x, step_y): (usize, usize)| {
+            !Self::is_border_with_size(i, j, width, height)
+                && !blocks[(i, j)]
+                && !blocks[(i - step_x, j - step_y)]
+        };
+        rotate_faces(
+            &self.config,
+            &mut self.horizontal_speeds,
+            &mut self.vertical_values,
+            is_fluid_face,
+            delta,
+        );
+    }
+
+    /// computes the inverse density of every face from the volume fraction of the cells
+    /// on both sides, the faces touching a block or the border are walls
+    fn update_face_weights(&mut self) {
+        let first_density = T::from_f32(self.config.density);
+        let second_density = T::from_f32(self.config.second_density);
+        let (width, height) = (self.width, self.height);
+        let Some(two_phase) = &mut self.two_phase else {
+            return;
+        };
+        let (blocks, phases) = (&self.block_grid, &two_phase.phases);
+
+        let is_wall = |(i, j): (usize, usize)| {
+            blocks[(i, j)] || Self::is_border_with_size(i, j, width, height)
+        };
+        let density =
+            |cell: (usize, usize)| first_density + (second_density - first_density) * phases[cell];
 
-        let half_size = 0.5;
-        (&mut new_horizontal, &mut new_vertical, &mut new_smoke)
+        let faces = [
+            (&mut two_phase.horizontal_face_weights, (1, 0)),
+            (&mut two_phase.vertical_face_weights, (0, 1)),
+        ];
+        for (weights, (step_x, step_y)) in faces {
+            weights
+                .as_mut_slice()
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(index, weight)| {
+                    let (i, j) = blocks.position(index);
+                    let before = i.checked_sub(step_x).zip(j.checked_sub(step_y));
+                    *weight = match before {
+                        Some(before) if !is_wall((i, j)) && !is_wall(before) => {
+                            T::from_f32(2.0) * first_density / (density((i, j)) + density(before))
+                        }
+                        _ => T::ZERO,
+                    };
+                });
+        }
+    }
+
+    /// copies the volume fraction of the cells next to the border into it,
+    /// so the border does not bring the phase it started with back into the tank
+    fn extend_phases_to_border(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let Some(two_phase) = &mut self.two_phase else {
+            return;
+        };
+        if width < 3 || height < 3 {
+            return;
+        }
+        let phases = &mut two_phase.phases;
+        for (i, j) in phases.positions() {
+            if !Self::is_border_with_size(i, j, width, height) {
+                continue;
+            }
+            let inside = (i.clamp(1, width - 2), j.clamp(1, height - 2));
+            phases[(i, j)] = phases[inside];
+        }
+    }
+
+    fn move_velocity(&mut self, delta: T) {
+        #[cfg(feature = "simd")]
+        if self.uses_kernels() {
+            self.move_by_columns(delta);
+            return;
+        }
+
+        let (mut new_horizontal, mut new_vertical) = self.take_velocity_buffers();
+
+        let h = T::from_f32(self.config.cell_size);
+        let half_size = T::from_f32(0.5) * h;
+        (new_horizontal.as_mut_slice(), new_vertical.as_mut_slice())
             .into_par_iter()
             .enumerate()
-            .for_each(|(index, (horizontal_value, vertical_value, smoke_value))| {
-                if self.block_grid[index] || self.index_is_border(index) {
+            .for_each(|(index, (horizontal_value, vertical_value))| {
+                let (i, j) = self.block_grid.position(index);
+                if self.block_grid[(i, j)] || self.is_border(i, j) {
                     return;
                 }
                 // for horizontal
-                let (i, j) = self.pos_from_index(index);
-                let mut x_pos = i as f32;
-                let mut y_pos = j as f32 + half_size;
+                let mut x_pos = T::from_usize(i) * h;
+                let mut y_pos = T::from_usize(j) * h + half_size;
                 let average_vertical_value = self.avg_vertical(i, j);
 
-                x_pos -= *horizontal_value * delta.as_secs_f32();
-                y_pos -= average_vertical_value * delta.as_secs_f32();
+                x_pos -= *horizontal_value * delta;
+                y_pos -= average_vertical_value * delta;
                 *horizontal_value = self.sample_vector(x_pos, y_pos, FieldType::Horizontal);
 
                 // for vertical component
-                x_pos = i as f32 + half_size;
-                y_pos = j as f32;
+                x_pos = T::from_usize(i) * h + half_size;
+                y_pos = T::from_usize(j) * h;
 
                 let average_horizontal_value = self.avg_horizontal(i, j);
 
-                x_pos -= average_horizontal_value * delta.as_secs_f32();
-                y_pos -= *vertical_value * delta.as_secs_f32();
+                x_pos -= average_horizontal_value * delta;
+                y_pos -= *vertical_value * delta;
 
                 *vertical_value = self.sample_vector(x_pos, y_pos, FieldType::Vertical);
+            });
+
+        // the smoke, the fire, the heat and the phases are carried by the velocities before the swap
+        let mut smoke = std::mem::take(&mut self.smoke_grid);
+        let mut smoke_buffer = std::mem::take(&mut self.smoke_buffer);
+        self.move_centered(&mut smoke, &mut smoke_buffer, delta);
+        (self.smoke_grid, self.smoke_buffer) = (smoke, smoke_buffer);
+        if let Some(mut fire) = self.fire.take() {
+            self.move_centered(&mut fire.fuel, &mut fire.fuel_buffer, delta);
+            self.fire = Some(fire);
+        }
+        if let Some(mut heat) = self.heat.take() {
+            self.move_centered(&mut heat.temperature, &mut heat.temperature_buffer, delta);
+            self.heat = Some(heat);
+        }
+        if let Some(mut two_phase) = self.two_phase.take() {
+            self.move_centered(&mut two_phase.phases, &mut two_phase.phase_buffer, delta);
+            self.two_phase = Some(two_phase);
+        }
+        self.swap_velocity_buffers(new_horizontal, new_vertical);
+    }
+
+    /// the advection of the `simd` feature, the sources of the faces and of the cell centers
+    /// are traced once for whole columns and every field stored there is sampled from them
+    #[cfg(feature = "simd")]
+    fn move_by_columns(&mut self, delta: T) {
+        let (mut new_horizontal, mut new_vertical) = self.take_velocity_buffers();
+        let (width, height) = (self.width, self.height);
+        let ratio = delta / T::from_f32(self.config.cell_size);
+        let (u, v) = (
+            self.horizontal_speeds.as_slice(),
+            self.vertical_values.as_slice(),
+        );
+        let blocks = self.block_grid.as_slice();
+        let advection = &mut self.advection;
+
+        advection.trace(u, v, width, height, ratio, Samples::Horizontal);
+        advection.sample(u, new_horizontal.as_mut_slice(), blocks, width, height);
+        advection.trace(u, v, width, height, ratio, Samples::Vertical);
+        advection.sample(v, new_vertical.as_mut_slice(), blocks, width, height);
+        advection.trace(u, v, width, height, ratio, Samples::Centered);
+
+        let advection = &*advection;
+        let move_centered = |values: &mut Grid<T, L>, buffer: &mut Grid<T, L>| {
+            buffer.as_mut_slice().copy_from_slice(values.as_slice());
+            advection.sample(
+                values.as_slice(),
+                buffer.as_mut_slice(),
+                blocks,
+                width,
+                height,
+            );
+            std::mem::swap(values, buffer);
+        };
+        move_centered(&mut self.smoke_grid, &mut self.smoke_buffer);
+        if let Some(fire) = &mut self.fire {
+            move_centered(&mut fire.fuel, &mut fire.fuel_buffer);
+        }
+        if let Some(heat) = &mut self.heat {
+            move_centered(&mut heat.temperature, &mut heat.temperature_buffer);
+        }
+        if let Some(two_phase) = &mut self.two_phase {
+            move_centered(&mut two_phase.phases, &mut two_phase.phase_buffer);
+        }
+        self.swap_velocity_buffers(new_horizontal, new_vertical);
+    }
 
-                // for smoke
-                if self.calculate_index(i + 1, j) < self.horizontal_speeds.len() {
-                    let cell_vertical_value = (self.vertical_values[index]
-                        + self.vertical_values[self.calculate_index(i, j + 1)])
-                        * 0.5;
-                    let cell_horizontal_value = (self.horizontal_speeds[index]
-                        + self.horizontal_speeds[self.calculate_index(i + 1, j)])
-                        * 0.5;
-
-                    x_pos = i as f32 + half_size - cell_horizontal_value * delta.as_secs_f32();
-                    y_pos = j as f32 + half_size - cell_vertical_value * delta.as_secs_f32();
-                    *smoke_value = self.sample_vector(x_pos, y_pos, FieldType::Smoke);
+    /// advects a field stored at the cell centers along the velocities through its back buffer,
+    /// the blocks and the border keep their values
+    fn move_centered(&self, values: &mut Grid<T, L>, buffer: &mut Grid<T, L>, delta: T) {
+        let h = T::from_f32(self.config.cell_size);
+        let half_size = T::from_f32(0.5) * h;
+        buffer.as_mut_slice().copy_from_slice(values.as_slice());
+        let source = &*values;
+        buffer
+            .as_mut_slice()
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, value)| {
+                let (i, j) = self.block_grid.position(index);
+                if self.block_grid[(i, j)] || self.is_border(i, j) {
+                    return;
                 }
+                let (u, v) = self.cell_velocity(i, j);
+                let x_pos = T::from_usize(i) * h + half_size - u * delta;
+                let y_pos = T::from_usize(j) * h + half_size - v * delta;
+                *value = self.sample_field(source, x_pos, y_pos, (half_size, half_size));
             });
-        self.horizontal_speeds = new_horizontal;
-        self.vertical_values = new_vertical;
-        self.smoke_grid = new_smoke;
+        std::mem::swap(values, buffer);
     }
 
-    fn avg_vertical(&self, i: usize, j: usize) -> f32 {
+    /// takes the velocity back buffers out of the sim filled with the current velocities
+    ///
+    /// cells that are not updated (blocks and borders) keep their current values this way
+    fn take_velocity_buffers(&mut self) -> (Grid<T, L>, Grid<T, L>) {
+        let mut new_horizontal = std::mem::take(&mut self.horizontal_buffer);
+        let mut new_vertical = std::mem::take(&mut self.vertical_buffer);
+        new_horizontal
+            .as_mut_slice()
+            .copy_from_slice(self.horizontal_speeds.as_slice());
+        new_vertical
+            .as_mut_slice()
+            .copy_from_slice(self.vertical_values.as_slice());
+        (new_horizontal, new_vertical)
+    }
+
+    /// makes the updated buffers the current velocities and keeps the old ones as back buffers
+    fn swap_velocity_buffers(&mut self, new_horizontal: Grid<T, L>, new_vertical: Grid<T, L>) {
+        self.horizontal_buffer = std::mem::replace(&mut self.horizontal_speeds, new_horizontal);
+        self.vertical_buffer = std::mem::replace(&mut self.vertical_values, new_vertical);
+    }
+
+    fn avg_vertical(&self, i: usize, j: usize) -> T {
         let v = &self.vertical_values;
-        let sum: f32 = [(i - 1, j), (i - 1, j + 1), (i, j + 1), (i, j)]
+        let sum: T = [(i - 1, j), (i - 1, j + 1), (i, j + 1), (i, j)]
             .into_iter()
-            .map(|(i, j)| {
-                let index = self.calculate_index(i, j);
-                v[index]
-            })
+            .map(|position| v[position])
             .sum();
-        sum * 0.25
+        sum * T::from_f32(0.25)
     }
 
-    fn avg_horizontal(&self, i: usize, j: usize) -> f32 {
+    fn avg_horizontal(&self, i: usize, j: usize) -> T {
         let u = &self.horizontal_speeds;
-        let sum: f32 = [(i, j - 1), (i + 1, j - 1), (i + 1, j), (i, j)]
+        let sum: T = [(i, j - 1), (i + 1, j - 1), (i + 1, j), (i, j)]
             .into_iter()
-            .map(|(i, j)| {
-                let index = self.calculate_index(i, j);
-                u[index]
-            })
+            .map(|position| u[position])
             .sum();
-        sum * 0.25
+        sum * T::from_f32(0.25)
     }
 
-    fn sample_vector(&self, x: f32, y: f32, field: FieldType) -> f32 {
-        let h = 1.0;
-        let x = x.min((self.width) as f32).max(h);
-        let y = y.min((self.height) as f32).max(h);
+    /// samples a velocity component at a position in meters
+    fn sample_vector(&self, x: T, y: T, field: FieldType) -> T {
+        let half_size = T::from_f32(0.5 * self.config.cell_size);
+        match field {
+            FieldType::Horizontal => {
+                self.sample_field(&self.horizontal_speeds, x, y, (T::ZERO, half_size))
+            }
+            FieldType::Vertical => {
+                self.sample_field(&self.vertical_values, x, y, (half_size, T::ZERO))
+            }
+        }
+    }
 
-        let inverse_size = 1.0 / h;
-        let half_size = 0.5 * h;
+    /// samples a field stored `(dx, dy)` from the bottom left corner of the cells
+    /// at a position in meters
+    fn sample_field(&self, field: &Grid<T, L>, x: T, y: T, (dx, dy): (T, T)) -> T {
+        let h = T::from_f32(self.config.cell_size);
+        let x = x.min(T::from_usize(self.width) * h).max(h);
+        let y = y.min(T::from_usize(self.height) * h).max(h);
 
-        let (field, dx, dy) = match field {
-            FieldType::Horizontal => (&self.horizontal_speeds, 0.0, half_size),
-            FieldType::Vertical => (&self.vertical_values, half_size, 0.0),
-            FieldType::Smoke => (&self.smoke_grid, half_size, half_size),
-        };
+        let inverse_size = T::ONE / h;
 
-        let x_left_index = (((x - dx) * inverse_size).floor() as usize).min(self.width - 1);
+        let x_left_index = ((x - dx) * inverse_size)
+            .floor()
+            .to_usize()
+            .min(self.width - 1);
         let x_right_index = (x_left_index + 1).min(self.width - 1);
-        let x_size_ratio = ((x - dx) - x_left_index as f32 * h) * inverse_size;
+        let x_size_ratio = ((x - dx) - T::from_usize(x_left_index) * h) * inverse_size;
 
-        let y_bottom_index = (((y - dy) * inverse_size).trunc() as usize).min(self.height - 1);
+        let y_bottom_index = ((y - dy) * inverse_size)
+            .trunc()
+            .to_usize()
+            .min(self.height - 1);
         let y_top_index = (y_bottom_index + 1).min(self.height - 1);
-        let y_size_ratio = ((y - dy) - y_bottom_index as f32 * h) * inverse_size;
+        let y_size_ratio = ((y - dy) - T::from_usize(y_bottom_index) * h) * inverse_size;
 
-        let sx = 1.0 - x_size_ratio;
-        let sy = 1.0 - y_size_ratio;
+        let sx = T::ONE - x_size_ratio;
+        let sy = T::ONE - y_size_ratio;
 
-        sx * sy * field[self.calculate_index(x_left_index, y_bottom_index)]
-            + x_size_ratio * sy * field[self.calculate_index(x_right_index, y_bottom_index)]
-            + x_size_ratio * y_size_ratio * field[self.calculate_index(x_right_index, y_top_index)]
-            + sx * y_size_ratio * field[self.calculate_index(x_left_index, y_top_index)]
+        sx * sy * field[(x_left_index, y_bottom_index)]
+            + x_size_ratio * sy * field[(x_right_index, y_bottom_index)]
+            + x_size_ratio * y_size_ratio * field[(x_right_index, y_top_index)]
+            + sx * y_size_ratio * field[(x_left_index, y_top_index)]
     }
 
     #[inline]
-    pub fn calculate_index(&self, x_index: usize, y_index: usize) -> usize {
-        Self::calculate_index_with_height(self.height, x_index, y_index)
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
     }
 
     #[inline]
-    pub fn calculate_index_with_height(height: usize, x_index: usize, y_index: usize) -> usize {
-        (x_index * height) + y_index
+    pub fn get_layout(&self) -> GridLayout {
+        L::LAYOUT
     }
 
+    /// returns the width and height of the simulated domain in meters
     #[inline]
-    pub fn get_size(&self) -> (usize, usize) {
-        (self.width, self.height)
+    pub fn get_domain_size(&self) -> (f32, f32) {
+        self.config.domain_size(self.width, self.height)
     }
 
     #[inline]
-    pub fn get_pressure_grid(&self) -> &Vec<f32> {
+    pub fn get_config(&self) -> &SimConfig {
+        &self.config
+    }
+
+    #[inline]
+    pub fn get_pressure_grid(&self) -> &Grid<T, L> {
         &self.pressure_grid
     }
 
     #[inline]
-    pub fn get_block_grid(&self) -> &Vec<bool> {
+    pub fn get_block_grid(&self) -> &Grid<bool, L> {
         &self.block_grid
     }
 
     #[inline]
-    pub fn get_smoke_grid(&self) -> &Vec<f32> {
+    pub fn get_smoke_grid(&self) -> &Grid<T, L> {
         &self.smoke_grid
     }
 
+    /// the fuel of the fire mode, 1 where a cell is full of it, `None` in the other modes
+    #[inline]
+    pub fn get_fuel_grid(&self) -> Option<&Grid<T, L>> {
+        self.fire.as_ref().map(|fire| &fire.fuel)
+    }
+
+    /// the temperature above the ambient one in K, only in the fire mode
+    /// and in the smoke mode with heat sources or walls holding a temperature
+    #[inline]
+    pub fn get_temperature_grid(&self) -> Option<&Grid<T, L>> {
+        self.heat.as_ref().map(|heat| &heat.temperature)
+    }
+
+    /// the volume fraction of the second fluid in the two phase mode, 1 where a cell is full of it,
+    /// `None` in the other modes
+    #[inline]
+    pub fn get_phase_grid(&self) -> Option<&Grid<T, L>> {
+        self.two_phase.as_ref().map(|two_phase| &two_phase.phases)
+    }
+
+    /// the horizontal speeds in m/s, stored on the left face of every cell
+    #[inline]
+    pub fn get_horizontal_speeds(&self) -> &Grid<T, L> {
+        &self.horizontal_speeds
+    }
+
+    /// the vertical speeds in m/s, stored on the bottom face of every cell
+    #[inline]
+    pub fn get_vertical_values(&self) -> &Grid<T, L> {
+        &self.vertical_values
+    }
+
+    /// returns the velocity in m/s at a position in meters from the bottom left corner
+    ///
+    /// each component is interpolated from the faces it is stored on
+    pub fn velocity_at(&self, x: T, y: T) -> (T, T) {
+        (
+            self.sample_vector(x, y, FieldType::Horizontal),
+            self.sample_vector(x, y, FieldType::Vertical),
+        )
+    }
+
+    /// returns the cell containing a position in meters, `None` outside of the grid
+    pub fn cell_at(&self, x: T, y: T) -> Option<(usize, usize)> {
+        if x < T::ZERO || y < T::ZERO {
+            return None;
+        }
+        let h = T::from_f32(self.config.cell_size);
+        let (x, y) = ((x / h).to_usize(), (y / h).to_usize());
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+
+    /// true if a position in meters is inside a block
+    pub(crate) fn is_block_at(&self, x: T, y: T) -> bool {
+        self.cell_at(x, y).is_some_and(|cell| self.block_grid[cell])
+    }
+
+    /// returns the velocity at the center of a cell by averaging the speeds on its faces
+    pub fn cell_velocity(&self, x: usize, y: usize) -> (T, T) {
+        let half = T::from_f32(0.5);
+        let right = (x + 1).min(self.width - 1);
+        let top = (y + 1).min(self.height - 1);
+        (
+            (self.horizontal_speeds[(x, y)] + self.horizontal_speeds[(right, y)]) * half,
+            (self.vertical_values[(x, y)] + self.vertical_values[(x, top)]) * half,
+        )
+    }
+
+    /// computes the velocity at the center of every cell
+    pub fn velocity_grid(&self) -> Grid<(T, T), L> {
+        Grid::from_fn(self.width, self.height, |x, y| self.cell_velocity(x, y))
+    }
+
+    /// computes the speed at the center of every cell
+    pub fn speed_grid(&self) -> Grid<T, L> {
+        Grid::from_fn(self.width, self.height, |x, y| {
+            let (u, v) = self.cell_velocity(x, y);
+            (u * u + v * v).sqrt()
+        })
+    }
+
+    /// returns the vorticity (the curl of the velocity) of a cell in 1/s,
+    /// positive when the fluid turns counterclockwise
+    ///
+    /// blocks and borders have no vorticity
+    pub fn vorticity_at(&self, x: usize, y: usize) -> T {
+        if self.block_grid[(x, y)] || self.is_border(x, y) {
+            return T::ZERO;
+        }
+        let two_h = T::from_f32(2.0 * self.config.cell_size);
+        let (_, right) = self.cell_velocity(x + 1, y);
+        let (_, left) = self.cell_velocity(x - 1, y);
+        let (top, _) = self.cell_velocity(x, y + 1);
+        let (bottom, _) = self.cell_velocity(x, y - 1);
+        (right - left) / two_h - (top - bottom) / two_h
+    }
+
+    /// returns the divergence of the velocity of a cell in 1/s,
+    /// which is close to 0 after the projection
+    ///
+    /// blocks and borders have no divergence
+    pub fn divergence_at(&self, x: usize, y: usize) -> T {
+        if self.block_grid[(x, y)] || self.is_border(x, y) {
+            return T::ZERO;
+        }
+        (self.horizontal_speeds[(x + 1, y)] - self.horizontal_speeds[(x, y)]
+            + self.vertical_values[(x, y + 1)]
+            - self.vertical_values[(x, y)])
+            / T::from_f32(self.config.cell_size)
+    }
+
+    /// computes the vorticity of every cell, see [`FluidSim::vorticity_at`]
+    pub fn vorticity_grid(&self) -> Grid<T, L> {
+        Grid::from_fn(self.width, self.height, |x, y| self.vorticity_at(x, y))
+    }
+
+    /// computes the divergence of every cell, see [`FluidSim::divergence_at`]
+    pub fn divergence_grid(&self) -> Grid<T, L> {
+        Grid::from_fn(self.width, self.height, |x, y| self.divergence_at(x, y))
+    }
+
+    /// the simulated time in seconds since the last restart
+    #[inline]
+    pub fn get_time(&self) -> T {
+        self.time
+    }
+
+    #[inline]
+    pub fn get_probes(&self) -> &[Probe<T>] {
+        &self.probes
+    }
+
+    /// adds a probe, returns false and ignores it when it is outside of the grid
+    /// or on a cell that already has one
+    pub fn add_probe(&mut self, probe: Probe<T>) -> bool {
+        let (x, y) = probe.position();
+        let is_taken = self.probes.iter().any(|other| other.position() == (x, y));
+        if x >= self.width || y >= self.height || is_taken {
+            return false;
+        }
+        self.probes.push(probe);
+        true
+    }
+
+    /// removes the probe on `(x, y)` if there is one
+    pub fn remove_probe(&mut self, x: usize, y: usize) -> Option<Probe<T>> {
+        let index = self
+            .probes
+            .iter()
+            .position(|probe| probe.position() == (x, y))?;
+        Some(self.probes.remove(index))
+    }
+
+    pub fn clear_probes(&mut self) {
+        self.probes.clear();
+    }
+
+    /// the particles of the liquid, `None` unless the sim is in the liquid mode
+    #[inline]
+    pub fn get_liquid(&self) -> Option<&Liquid<T, L>> {
+        self.liquid.as_ref()
+    }
+
+    #[inline]
+    pub fn get_tracers(&self) -> &Tracers<T> {
+        &self.tracers
+    }
+
+    /// the tracers and their emitters, which can be added and configured through it
+    #[inline]
+    pub fn get_tracers_mut(&mut self) -> &mut Tracers<T> {
+        &mut self.tracers
+    }
+
+    /// starts or stops accumulating the mean and the fluctuations of the fields after every step,
+    /// stopping drops the statistics
+    pub fn enable_statistics(&mut self, enabled: bool) {
+        if !enabled {
+            self.statistics = None;
+        } else if self.statistics.is_none() {
+            self.start_statistics();
+        }
+    }
+
+    /// the statistics since they were enabled or last reset, `None` when they are disabled
+    #[inline]
+    pub fn get_statistics(&self) -> Option<&FieldStatistics<T, L>> {
+        self.statistics.as_ref()
+    }
+
+    /// restarts the statistics from no samples if they are enabled
+    pub fn reset_statistics(&mut self) {
+        if self.statistics.is_some() {
+            self.start_statistics();
+        }
+    }
+
+    fn start_statistics(&mut self) {
+        self.statistics = Some(FieldStatistics::new(self.width, self.height));
+    }
+
     #[inline]
     pub fn set_block(&mut self, x: usize, y: usize) {
-        let index = self.calculate_index(x, y);
-        self.block_grid[index] = true;
+        if let Some(cell) = self.block_grid.get_mut(x, y) {
+            *cell = true;
+        }
     }
 
     #[inline]
     pub fn unset_block(&mut self, x: usize, y: usize) {
-        let index = self.calculate_index(x, y);
-        self.block_grid[index] = false;
+        if let Some(cell) = self.block_grid.get_mut(x, y) {
+            *cell = false;
+        }
     }
 
-    /// calculates the indexes and returns the in the top, right, bottom, left order
-    fn indexes_around(&self, x_index: usize, y_index: usize) -> [usize; 4] {
-        [
-            self.calculate_index(x_index, y_index + 1),
-            self.calculate_index(x_index + 1, y_index),
-            self.calculate_index(x_index, y_index - 1),
-            self.calculate_index(x_index - 1, y_index),
-        ]
+    /// whether the mode carries a temperature the heaters, coolers and walls act on
+    #[inline]
+    pub fn simulates_heat(&self) -> bool {
+        matches!(self.config.mode, SimMode::Smoke | SimMode::Fire)
     }
 
+    /// the heaters and coolers of every cell
     #[inline]
-    fn pos_from_index(&self, index: usize) -> (usize, usize) {
-        let x = index / self.height;
-        let y = index % self.height;
-        (x, y)
+    pub fn get_heat_sources(&self) -> &Grid<Option<HeatSource>, L> {
+        &self.heat_sources
     }
 
     #[inline]
-    fn index_is_border(&self, index: usize) -> bool {
-        Self::index_is_border_with_size(index, self.width, self.height)
+    pub fn set_heat_source(&mut self, x: usize, y: usize, source: HeatSource) {
+        if let Some(cell) = self.heat_sources.get_mut(x, y) {
+            if cell.replace(source).is_none() {
+                self.heat_source_count += 1;
+            }
+        }
+        self.update_heat();
     }
 
     #[inline]
-    fn index_is_border_with_size(index: usize, width: usize, height: usize) -> bool {
-        let is_left_border = index < height;
-        let is_right_border = index >= (width - 1) * height;
+    pub fn unset_heat_source(&mut self, x: usize, y: usize) {
+        if let Some(cell) = self.heat_sources.get_mut(x, y) {
+            if cell.take().is_some() {
+                self.heat_source_count -= 1;
+            }
+        }
+        self.update_heat();
+    }
 
-        let remainder = index % height;
-        let is_top_border = remainder == height - 1;
-        let is_bottom_border = remainder == 0;
+    /// returns 1 for a fluid cell and 0 for a block
+    #[inline]
+    fn fluid_factor(is_block: bool) -> T {
+        if is_block {
+            T::ZERO
+        } else {
+            T::ONE
+        }
+    }
+
+    /// returns the positions around a cell in the top, right, bottom, left order
+    fn positions_around(&self, x_index: usize, y_index: usize) -> [(usize, usize); 4] {
+        [
+            (x_index, y_index + 1),
+            (x_index + 1, y_index),
+            (x_index, y_index - 1),
+            (x_index - 1, y_index),
+        ]
+    }
 
+    #[inline]
+    fn is_border(&self, x: usize, y: usize) -> bool {
+        Self::is_border_with_size(x, y, self.width, self.height)
+    }
+
+    #[inline]
+    fn is_border_with_size(x: usize, y: usize, width: usize, height: usize) -> bool {
         // return true if any are true
-        is_left_border || is_right_border || is_top_border || is_bottom_border
+        x == 0 || x == width - 1 || y == 0 || y == height - 1
     }
 }
 
 enum FieldType {
     Horizontal,
     Vertical,
-    Smoke,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STANDARD_GRAVITY;
+
+    /// the relative difference of two values, 0 when both are 0
+    fn relative_difference(a: f64, b: f64) -> f64 {
+        let scale = a.abs().max(b.abs());
+        if scale == 0.0 {
+            0.0
+        } else {
+            (a - b).abs() / scale
+        }
+    }
+
+    #[test]
+    fn single_and_double_precision_agree_on_a_fixed_step() {
+        let config = SimConfig::default();
+        let mut single = FluidSim::<f32>::new(40, 20, config.clone());
+        let mut double = FluidSim::<f64>::new(40, 20, config);
+        for y in 8..12 {
+            single.set_block(12, y);
+            double.set_block(12, y);
+        }
+
+        for _ in 0..50 {
+            let single = single.step(0.01);
+            let double = double.step(0.01);
+            assert!(single.kinetic_energy.is_finite());
+            assert!(
+                relative_difference(single.kinetic_energy as f64, double.kinetic_energy) < 1e-3
+            );
+            assert!(relative_difference(single.smoke_mass as f64, double.smoke_mass) < 1e-3);
+        }
+        assert!((single.get_time() as f64 - double.get_time()).abs() < 1e-5);
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn the_vectorized_and_scalar_projections_agree() {
+        let config = SimConfig::default();
+        let mut vectorized = FluidSim::<f64>::new(40, 20, config.clone());
+        let mut scalar = FluidSim::<f64>::new(40, 20, config);
+        scalar.set_vectorized(false);
+        for y in 8..12 {
+            vectorized.set_block(12, y);
+            scalar.set_block(12, y);
+        }
+
+        for _ in 0..20 {
+            vectorized.step(0.01);
+            scalar.step(0.01);
+        }
+        // the red black order converges to the same pressure without matching bit for bit
+        let pressures = vectorized.get_pressure_grid().iter();
+        let max_pressure = pressures.clone().fold(0.0, |max: f64, p| max.max(p.abs()));
+        let max_difference = pressures
+            .zip(scalar.get_pressure_grid().iter())
+            .fold(0.0, |max: f64, (a, b)| max.max((a - b).abs()));
+        assert!(
+            max_difference < 1e-3 * max_pressure,
+            "{max_difference} Pa apart"
+        );
+    }
+
+    #[test]
+    fn a_fixed_step_is_reproducible() {
+        let mut first = FluidSim::<f64>::new(30, 15, SimConfig::default());
+        let mut second = FluidSim::<f64>::new(30, 15, SimConfig::default());
+        for _ in 0..20 {
+            first.step(0.02);
+            second.step(0.02);
+        }
+        assert_eq!(first.get_smoke_grid(), second.get_smoke_grid());
+        assert_eq!(
+            first.get_horizontal_speeds(),
+            second.get_horizontal_speeds()
+        );
+    }
+
+    #[test]
+    fn a_domain_width_keeps_the_domain_when_resizing() {
+        let config = SimConfig {
+            domain_width: Some(0.8),
+            ..SimConfig::default()
+        };
+        let mut sim = FluidSim::<f32>::new(40, 20, config);
+        assert!((sim.get_config().cell_size - 0.02).abs() < 1e-6);
+
+        sim.resize(80, 40);
+        let (width, height) = sim.get_domain_size();
+        assert!((sim.get_config().cell_size - 0.01).abs() < 1e-6);
+        assert!((width - 0.8).abs() < 1e-5 && (height - 0.4).abs() < 1e-5);
+    }
+
+    #[test]
+    fn heaters_warm_and_coolers_chill_the_fluid_around_them() {
+        let config = SimConfig {
+            wind_speed: 0.0,
+            ..SimConfig::default()
+        };
+        let mut sim = FluidSim::<f64>::new(30, 20, config);
+        assert!(sim.get_temperature_grid().is_none());
+        sim.set_heat_source(10, 10, HeatSource::Heater);
+        sim.set_heat_source(20, 10, HeatSource::Cooler);
+        for _ in 0..20 {
+            sim.step(0.01);
+        }
+
+        let heater_temperature = f64::from(sim.get_config().heater_temperature);
+        let temperature = sim.get_temperature_grid().unwrap();
+        assert_eq!(temperature[(10, 10)], heater_temperature);
+        assert_eq!(temperature[(20, 10)], -heater_temperature);
+        // the warm fluid rises above the heater and the cold one sinks below the cooler
+        assert!(temperature[(10, 11)] > 0.0);
+        assert!(temperature[(20, 9)] < 0.0);
+
+        // without sources nor conducting walls the temperature is dropped
+        sim.unset_heat_source(10, 10);
+        sim.unset_heat_source(20, 10);
+        assert!(sim.get_temperature_grid().is_none());
+    }
+
+    #[test]
+    fn conducting_walls_hold_their_temperature_and_warm_the_fluid() {
+        let config = SimConfig {
+            wind_speed: 0.0,
+            wall_temperature: Some(50.0),
+            ..SimConfig::default()
+        };
+        let mut sim = FluidSim::<f64>::new(30, 20, config);
+        for y in 5..15 {
+            sim.set_block(15, y);
+        }
+        for _ in 0..20 {
+            sim.step(0.01);
+        }
+
+        let temperature = sim.get_temperature_grid().unwrap();
+        assert_eq!(temperature[(15, 10)], 50.0);
+        assert!(temperature[(14, 10)] > 0.0 && temperature[(16, 10)] > 0.0);
+        assert!(temperature[(14, 10)] < 50.0);
+        // the heat has not reached the fluid far from the wall yet
+        assert!(temperature[(5, 10)].abs() < 1e-6);
+    }
+
+    /// the height of the center of the second fluid in cells
+    fn second_phase_height(sim: &FluidSim<f64>) -> f64 {
+        let phases = sim.get_phase_grid().unwrap();
+        let (volume, moment) = phases
+            .positions()
+            .fold((0.0, 0.0), |(volume, moment), (x, y)| {
+                let phase = phases[(x, y)];
+                (volume + phase, moment + phase * (y as f64 + 0.5))
+            });
+        moment / volume
+    }
+
+    #[test]
+    fn the_heavy_fluid_sinks_through_the_light_one() {
+        let config = SimConfig {
+            mode: SimMode::TwoPhase,
+            gravity: (0.0, -STANDARD_GRAVITY),
+            ..SimConfig::default()
+        };
+        assert!(config.second_density > config.density);
+        let mut sim = FluidSim::<f64>::new(24, 24, config);
+        let start = second_phase_height(&sim);
+        for _ in 0..50 {
+            sim.step(0.01);
+        }
+        let end = second_phase_height(&sim);
+        assert!(
+            end < start - 1.0,
+            "the center of the heavy fluid went from {start} to {end}"
+        );
+    }
+
+    #[test]
+    fn a_flat_interface_under_the_light_fluid_stays_flat() {
+        let config = SimConfig {
+            mode: SimMode::TwoPhase,
+            gravity: (0.0, -STANDARD_GRAVITY),
+            second_density: 500.0,
+            ..SimConfig::default()
+        };
+        let mut sim = FluidSim::<f64>::new(24, 24, config);
+        // the light fluid fills the top half
+        let phases = &mut sim.two_phase.as_mut().unwrap().phases;
+        for (x, y) in phases.positions() {
+            phases[(x, y)] = if y >= 12 { 1.0 } else { 0.0 };
+        }
+        let start = second_phase_height(&sim);
+        let mut diagnostics = StepDiagnostics::default();
+        for _ in 0..50 {
+            diagnostics = sim.step(0.01);
+        }
+        assert!(
+            diagnostics.max_speed < 1e-2,
+            "speed {}",
+            diagnostics.max_speed
+        );
+        assert!((second_phase_height(&sim) - start).abs() < 1e-2);
+        // every cell is still full of one of the fluids
+        let phases = sim.get_phase_grid().unwrap();
+        let mixed = phases.iter().map(|&phase| phase.min(1.0 - phase));
+        assert!(mixed.fold(0.0, f64::max) < 1e-2);
+    }
 }
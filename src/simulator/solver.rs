@@ -0,0 +1,177 @@
+use crate::{ColumnMajor, FluidSim, Grid, Layout, Scalar, SimConfig, SimMode, StepDiagnostics};
+
+/// how the cells of a solver are meant to be colored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shading {
+    /// the pressure with a color gradient, darkened where there is no smoke
+    #[default]
+    Smoke,
+
+    /// the cells filled with liquid in blue, deeper where the pressure is higher
+    Liquid,
+
+    /// the pressure as the height of a water surface lit from the top left
+    Height,
+
+    /// the smoke in gray with the flames glowing by their temperature
+    Fire,
+
+    /// the two fluids of the two phase mode in their own colors
+    Phases,
+}
+
+/// a simulation engine that can be stepped, edited and drawn without knowing how it works
+///
+/// every solver samples its state on a grid of `cell_size` meters with the origin at the bottom left,
+/// so the renderer and the editor work with all of them. the features that only exist
+/// on the staggered grid (probes, tracers, statistics, forces) are reached through
+/// [`Solver::as_fluid_sim`]
+pub trait Solver<T: Scalar = f32, L: Layout = ColumnMajor> {
+    /// the name of the solver shown to the user
+    fn name(&self) -> &'static str;
+
+    /// advances the simulation by the time elapsed since the last step
+    /// and returns the diagnostics of the new fields
+    fn next_step(&mut self) -> StepDiagnostics<T>;
+
+    /// restarts the simulation on a grid of the given size, keeping what fits of the blocks
+    fn resize(&mut self, width: usize, height: usize);
+
+    fn restart_sim(&mut self);
+
+    fn get_size(&self) -> (usize, usize);
+
+    fn get_config(&self) -> &SimConfig;
+
+    fn set_config(&mut self, config: SimConfig);
+
+    fn get_block_grid(&self) -> &Grid<bool, L>;
+
+    fn set_block(&mut self, x: usize, y: usize);
+
+    fn unset_block(&mut self, x: usize, y: usize);
+
+    /// the value shaded in every cell, the pressure in Pa for the smoke and the liquid
+    fn get_pressure_grid(&self) -> &Grid<T, L>;
+
+    /// 1 where a cell is empty and 0 where it is full of smoke or liquid
+    fn get_smoke_grid(&self) -> &Grid<T, L>;
+
+    /// returns the velocity in m/s at a position in meters from the bottom left corner
+    fn velocity_at(&self, x: T, y: T) -> (T, T);
+
+    fn shading(&self) -> Shading;
+
+    /// drops some water on the cell `(x, y)`, the solvers without a free surface ignore it
+    fn add_droplet(&mut self, _x: usize, _y: usize) {}
+
+    /// returns the width and height of the simulated domain in meters
+    fn get_domain_size(&self) -> (f32, f32) {
+        let (width, height) = self.get_size();
+        self.get_config().domain_size(width, height)
+    }
+
+    /// the kinematic viscosity in m²/s the solver simulates, higher than the one of the config
+    /// when the solver cannot go that low on its grid
+    fn effective_viscosity(&self) -> f32 {
+        self.get_config().viscosity
+    }
+
+    /// returns the Reynolds number of the flow using the wind speed, the effective viscosity
+    /// and the height of the domain as the characteristic length
+    fn reynolds_number(&self) -> f32 {
+        let (_, domain_height) = self.get_domain_size();
+        let viscosity = self.effective_viscosity();
+        if viscosity <= 0.0 {
+            return f32::INFINITY;
+        }
+        self.get_config().wind_speed * domain_height / viscosity
+    }
+
+    /// the solver as the staggered grid simulation if it is one
+    fn as_fluid_sim(&self) -> Option<&FluidSim<T, L>> {
+        None
+    }
+
+    fn as_fluid_sim_mut(&mut self) -> Option<&mut FluidSim<T, L>> {
+        None
+    }
+}
+
+impl<T: Scalar, L: Layout> Solver<T, L> for FluidSim<T, L> {
+    fn name(&self) -> &'static str {
+        match self.get_config().mode {
+            SimMode::Liquid => "PIC/FLIP",
+            SimMode::Smoke | SimMode::Fire | SimMode::TwoPhase => "Eulerian",
+        }
+    }
+
+    fn next_step(&mut self) -> StepDiagnostics<T> {
+        FluidSim::next_step(self)
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        FluidSim::resize(self, width, height);
+    }
+
+    fn restart_sim(&mut self) {
+        FluidSim::restart_sim(self);
+    }
+
+    fn get_size(&self) -> (usize, usize) {
+        FluidSim::get_size(self)
+    }
+
+    fn get_config(&self) -> &SimConfig {
+        FluidSim::get_config(self)
+    }
+
+    fn set_config(&mut self, config: SimConfig) {
+        FluidSim::set_config(self, config);
+    }
+
+    fn get_block_grid(&self) -> &Grid<bool, L> {
+        FluidSim::get_block_grid(self)
+    }
+
+    fn set_block(&mut self, x: usize, y: usize) {
+        FluidSim::set_block(self, x, y);
+    }
+
+    fn unset_block(&mut self, x: usize, y: usize) {
+        FluidSim::unset_block(self, x, y);
+    }
+
+    fn get_pressure_grid(&self) -> &Grid<T, L> {
+        FluidSim::get_pressure_grid(self)
+    }
+
+    fn get_smoke_grid(&self) -> &Grid<T, L> {
+        FluidSim::get_smoke_grid(self)
+    }
+
+    fn velocity_at(&self, x: T, y: T) -> (T, T) {
+        FluidSim::velocity_at(self, x, y)
+    }
+
+    fn shading(&self) -> Shading {
+        match self.get_config().mode {
+            SimMode::Smoke => Shading::Smoke,
+            SimMode::Liquid => Shading::Liquid,
+            SimMode::Fire => Shading::Fire,
+            SimMode::TwoPhase => Shading::Phases,
+        }
+    }
+
+    fn get_domain_size(&self) -> (f32, f32) {
+        FluidSim::get_domain_size(self)
+    }
+
+    fn as_fluid_sim(&self) -> Option<&FluidSim<T, L>> {
+        Some(self)
+    }
+
+    fn as_fluid_sim_mut(&mut self) -> Option<&mut FluidSim<T, L>> {
+        Some(self)
+    }
+}
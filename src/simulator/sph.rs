@@ -0,0 +1,704 @@
+use rayon::prelude::*;
+use std::f32::consts::PI;
+use std::time::Instant;
+
+use super::{
+    diagnostics::{cell_diagnostics, cell_velocity_at},
+    liquid::{is_solid, stencil},
+};
+use crate::{
+    ColumnMajor, Grid, Layout, LiquidParticle, Scalar, Shading, SimConfig, Solver, StepDiagnostics,
+};
+
+/// the number of particles in each direction of a cell when the tank is filled
+const PARTICLES_PER_SIDE: usize = 2;
+
+/// the radius of the smoothing kernels in cells
+const KERNEL_RADIUS: f32 = 1.0;
+
+/// the speed of sound compared to the speed of the water falling from the top of the tank,
+/// the density of the water varies by about the inverse of its square
+const SOUND_SPEED_RATIO: f32 = 5.0;
+
+/// the viscosity added to the one of the config, as a fraction of the kernel radius
+/// times the speed of sound, it damps the noise of the particles
+const ARTIFICIAL_VISCOSITY: f32 = 0.01;
+
+/// the most substeps taken in one step, a slower frame drops the time that does not fit
+const MAX_SUBSTEPS: usize = 16;
+
+/// a weakly compressible smoothed particle hydrodynamics (SPH) solver
+///
+/// the water is made of particles that carry their own density, computed from the particles
+/// around them, and push each other apart when they are denser than the water at rest.
+/// no grid is needed to move them, the fields of the solver are only filled to draw them
+pub struct Sph<T: Scalar = f32, L: Layout = ColumnMajor> {
+    particles: Vec<LiquidParticle<T>>,
+
+    /// the density of every particle in kg/m³
+    densities: Vec<T>,
+
+    /// the pressure of every particle in Pa
+    pressures: Vec<T>,
+
+    /// the acceleration of every particle in m/s²
+    accelerations: Vec<(T, T)>,
+
+    /// the mass of every particle per meter of depth in kg/m,
+    /// chosen so the particles of the filled tank are at the density of the config
+    particle_mass: T,
+
+    /// finds the particles closer than the kernel radius
+    hash: SpatialHash<T>,
+
+    /// the pressure of the particles around the center of every cell in Pa
+    pressure_grid: Grid<T, L>,
+
+    /// 1 where the cells are empty and 0 where they are full of particles
+    smoke_grid: Grid<T, L>,
+
+    /// the velocity of the particles around the center of every cell in m/s
+    velocity_grid: Grid<(T, T), L>,
+
+    /// the weight of the particles around the center of every cell
+    weights: Grid<T, L>,
+    block_grid: Grid<bool, L>,
+
+    width: usize,
+    height: usize,
+
+    // the last time the particles have been calculated
+    last_instant: Instant,
+
+    config: SimConfig,
+}
+
+impl<T: Scalar, L: Layout> Default for Sph<T, L> {
+    fn default() -> Self {
+        Sph::new(2, 2, SimConfig::default())
+    }
+}
+
+impl<T: Scalar, L: Layout> Sph<T, L> {
+    pub fn new(width: usize, height: usize, mut config: SimConfig) -> Self {
+        config.fit_domain(width);
+        let zeros = Grid::new(width, height, T::ZERO);
+        let mut sph = Self {
+            particles: Vec::new(),
+            densities: Vec::new(),
+            pressures: Vec::new(),
+            accelerations: Vec::new(),
+            particle_mass: T::ZERO,
+            hash: SpatialHash::default(),
+            pressure_grid: zeros.clone(),
+            smoke_grid: Grid::new(width, height, T::ONE),
+            velocity_grid: Grid::new(width, height, (T::ZERO, T::ZERO)),
+            weights: zeros,
+            block_grid: Grid::new(width, height, false),
+            width,
+            height,
+            last_instant: Instant::now(),
+            config,
+        };
+        sph.fill_water();
+        sph
+    }
+
+    #[inline]
+    pub fn particles(&self) -> &[LiquidParticle<T>] {
+        &self.particles
+    }
+
+    /// the radius of the kernels in meters
+    #[inline]
+    fn kernel_radius(&self) -> T {
+        T::from_f32(KERNEL_RADIUS * self.config.cell_size)
+    }
+
+    /// the distance between two particles of the filled tank in meters
+    #[inline]
+    fn spacing(&self) -> T {
+        T::from_f32(self.config.cell_size) / T::from_usize(PARTICLES_PER_SIDE)
+    }
+
+    fn gravity(&self) -> (T, T) {
+        let (x, y) = self.config.water_gravity();
+        (T::from_f32(x), T::from_f32(y))
+    }
+
+    /// the center of the rotating frame in meters
+    fn center(&self) -> (T, T) {
+        let (width, height) = self.config.domain_size(self.width, self.height);
+        (T::from_f32(0.5 * width), T::from_f32(0.5 * height))
+    }
+
+    /// the speed of sound in m/s, fast enough compared to the water falling across the tank
+    /// or thrown to its corners by the rotation
+    fn sound_speed(&self) -> T {
+        let (width, height) = self.config.domain_size(self.width, self.height);
+        let (gravity_x, gravity_y) = self.config.water_gravity();
+        let spin_speed = self.config.rotation_rate * 0.5 * width.hypot(height);
+        let fall_speed = (2.0 * (gravity_x.abs() * width + gravity_y.abs() * height)
+            + spin_speed * spin_speed)
+            .sqrt();
+        T::from_f32(SOUND_SPEED_RATIO * fall_speed)
+    }
+
+    /// fills the bottom left of the tank with particles at rest, like a dam about to break
+    fn fill_water(&mut self) {
+        let h = T::from_f32(self.config.cell_size);
+        let spacing = self.spacing();
+        let half = T::from_f32(0.5);
+
+        self.particles.clear();
+        for (x, y) in self.block_grid.positions() {
+            let is_dam = x * 5 < self.width * 2 && y * 5 < self.height * 3;
+            if !is_dam || is_solid(&self.block_grid, x, y) {
+                continue;
+            }
+            for i in 0..PARTICLES_PER_SIDE {
+                for j in 0..PARTICLES_PER_SIDE {
+                    self.particles.push(LiquidParticle {
+                        position: (
+                            T::from_usize(x) * h + (T::from_usize(i) + half) * spacing,
+                            T::from_usize(y) * h + (T::from_usize(j) + half) * spacing,
+                        ),
+                        velocity: (T::ZERO, T::ZERO),
+                    });
+                }
+            }
+        }
+
+        // the density of a particle inside a lattice of particles is the one of the water at rest
+        let radius = self.kernel_radius();
+        let reach = (KERNEL_RADIUS * PARTICLES_PER_SIDE as f32).ceil() as isize;
+        let mut rest_sum = T::ZERO;
+        for i in -reach..=reach {
+            for j in -reach..=reach {
+                let (x, y) = (
+                    T::from_f32(i as f32) * spacing,
+                    T::from_f32(j as f32) * spacing,
+                );
+                rest_sum += density_kernel(x * x + y * y, radius);
+            }
+        }
+        self.particle_mass = T::from_f32(self.config.density) / rest_sum;
+        self.update_fields();
+    }
+
+    /// the longest substep that keeps the sound under a fraction of the kernel radius per substep
+    /// and the viscosity stable
+    fn max_substep(&self) -> T {
+        let max_speed = self
+            .particles
+            .par_iter()
+            .map(|particle| {
+                let (u, v) = particle.velocity;
+                (u * u + v * v).sqrt()
+            })
+            .reduce(|| T::ZERO, T::max);
+        let radius = self.kernel_radius();
+        let sound = T::from_f32(0.4) * radius / (self.sound_speed() + max_speed);
+        let viscous = T::from_f32(0.125) * radius * radius / self.viscosity();
+        sound.min(viscous)
+    }
+
+    /// the kinematic viscosity of the particles in m²/s
+    fn viscosity(&self) -> T {
+        T::from_f32(self.config.viscosity)
+            + T::from_f32(ARTIFICIAL_VISCOSITY) * self.kernel_radius() * self.sound_speed()
+    }
+
+    fn substep(&mut self, delta: T) {
+        let radius = self.kernel_radius();
+        self.hash.sort(&mut self.particles, radius);
+        self.update_densities(radius);
+        self.update_accelerations(radius);
+        self.move_particles(delta);
+    }
+
+    /// sums the kernel of the neighbors of every particle and deduces its pressure
+    /// with the Tait equation, the particles do not pull each other so the pressure is positive
+    fn update_densities(&mut self, radius: T) {
+        let rest_density = T::from_f32(self.config.density);
+        let sound_speed = self.sound_speed();
+        let stiffness = rest_density * sound_speed * sound_speed / T::from_f32(7.0);
+        let (particles, hash, mass) = (&self.particles, &self.hash, self.particle_mass);
+
+        self.densities.resize(self.particles.len(), rest_density);
+        self.pressures.resize(self.particles.len(), T::ZERO);
+        self.densities
+            .par_iter_mut()
+            .zip(self.pressures.par_iter_mut())
+            .zip(particles.par_iter())
+            .for_each(|((density, pressure), particle)| {
+                let (x, y) = particle.position;
+                let mut sum = T::ZERO;
+                hash.for_each_neighbor(particle.position, radius, |index| {
+                    let (other_x, other_y) = particles[index].position;
+                    let (dx, dy) = (x - other_x, y - other_y);
+                    sum += density_kernel(dx * dx + dy * dy, radius);
+                });
+                *density = mass * sum;
+
+                let ratio = *density / rest_density;
+                let squared = ratio * ratio;
+                let seventh = squared * squared * squared * ratio;
+                *pressure = (stiffness * (seventh - T::ONE)).max(T::ZERO);
+            });
+    }
+
+    /// adds the gravity, the forces of the rotating frame, the pressure pushing the particles
+    /// apart and the viscosity bringing their velocities together
+    fn update_accelerations(&mut self, radius: T) {
+        let gravity = self.gravity();
+        let center = self.center();
+        let config = &self.config;
+        let viscosity = self.viscosity();
+        let mass = self.particle_mass;
+        let (particles, densities, pressures, hash) = (
+            &self.particles,
+            &self.densities,
+            &self.pressures,
+            &self.hash,
+        );
+        let min_squared_distance = radius * radius * T::from_f32(1e-6);
+
+        self.accelerations
+            .resize(self.particles.len(), (T::ZERO, T::ZERO));
+        self.accelerations
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(i, acceleration)| {
+                let particle = &particles[i];
+                let (x, y) = particle.position;
+                let (u, v) = particle.velocity;
+                let pressure_term = pressures[i] / (densities[i] * densities[i]);
+                let (frame_x, frame_y) =
+                    config.frame_acceleration((x - center.0, y - center.1), (u, v));
+                let (mut ax, mut ay) = (gravity.0 + frame_x, gravity.1 + frame_y);
+
+                hash.for_each_neighbor(particle.position, radius, |j| {
+                    let other = &particles[j];
+                    let (dx, dy) = (x - other.position.0, y - other.position.1);
+                    let squared_distance = dx * dx + dy * dy;
+                    if i == j
+                        || squared_distance >= radius * radius
+                        || squared_distance < min_squared_distance
+                    {
+                        return;
+                    }
+                    let distance = squared_distance.sqrt();
+
+                    let push = -mass
+                        * (pressure_term + pressures[j] / (densities[j] * densities[j]))
+                        * pressure_kernel_slope(distance, radius)
+                        / distance;
+                    let blend = viscosity * mass / densities[j]
+                        * viscosity_kernel_laplacian(distance, radius);
+                    ax += push * dx + blend * (other.velocity.0 - u);
+                    ay += push * dy + blend * (other.velocity.1 - v);
+                });
+                *acceleration = (ax, ay);
+            });
+    }
+
+    /// moves the particles by their velocity, stopping them on the solid cells
+    fn move_particles(&mut self, delta: T) {
+        let h = T::from_f32(self.config.cell_size);
+        let blocks = &self.block_grid;
+        let is_solid_at = |(x, y): (T, T)| {
+            x < T::ZERO || y < T::ZERO || {
+                let (i, j) = ((x / h).to_usize(), (y / h).to_usize());
+                !blocks.contains(i, j) || is_solid(blocks, i, j)
+            }
+        };
+
+        self.particles
+            .par_iter_mut()
+            .zip(self.accelerations.par_iter())
+            .for_each(|(particle, &(ax, ay))| {
+                particle.velocity.0 += ax * delta;
+                particle.velocity.1 += ay * delta;
+                let (x, y) = particle.position;
+                let (u, v) = particle.velocity;
+                let (mut new_x, mut new_y) = (x + u * delta, y + v * delta);
+
+                // the velocity into a wall is lost, the water slides along it
+                if is_solid_at((new_x, new_y)) {
+                    if is_solid_at((new_x, y)) {
+                        new_x = x;
+                        particle.velocity.0 = T::ZERO;
+                    }
+                    if is_solid_at((x, new_y)) {
+                        new_y = y;
+                        particle.velocity.1 = T::ZERO;
+                    }
+                    if is_solid_at((new_x, new_y)) {
+                        (new_x, new_y) = (x, y);
+                        particle.velocity = (T::ZERO, T::ZERO);
+                    }
+                }
+                particle.position = (new_x, new_y);
+            });
+    }
+
+    /// spreads the particles on the centers of the cells to fill the fields that are drawn,
+    /// a cell holding as many particles as the filled tank is full
+    fn update_fields(&mut self) {
+        let h = T::from_f32(self.config.cell_size);
+        let half = T::from_f32(0.5) * h;
+
+        self.weights.fill(T::ZERO);
+        self.pressure_grid.fill(T::ZERO);
+        self.velocity_grid.fill((T::ZERO, T::ZERO));
+        for (index, particle) in self.particles.iter().enumerate() {
+            let pressure = self.pressures.get(index).copied().unwrap_or(T::ZERO);
+            let (u, v) = particle.velocity;
+            for (cell, weight) in stencil(&self.weights, particle.position, (half, half), h) {
+                self.weights[cell] += weight;
+                self.pressure_grid[cell] += weight * pressure;
+                let velocity = &mut self.velocity_grid[cell];
+                *velocity = (velocity.0 + weight * u, velocity.1 + weight * v);
+            }
+        }
+
+        let full = T::from_usize(PARTICLES_PER_SIDE * PARTICLES_PER_SIDE);
+        for (index, &weight) in self.weights.iter().enumerate() {
+            self.smoke_grid.as_mut_slice()[index] = T::ONE - (weight / full).min(T::ONE);
+            if weight > T::ZERO {
+                self.pressure_grid.as_mut_slice()[index] /= weight;
+                let velocity = &mut self.velocity_grid.as_mut_slice()[index];
+                *velocity = (velocity.0 / weight, velocity.1 / weight);
+            }
+        }
+    }
+
+    /// computes the diagnostics of the particles, the volume of the water is counted
+    /// as the smoke in m² and the vorticity and divergence are taken from the velocity of the cells
+    pub fn diagnostics(&self) -> StepDiagnostics<T> {
+        let half = T::from_f32(0.5);
+        let mass = self.particle_mass;
+        let rest_density = T::from_f32(self.config.density);
+
+        let particles = self
+            .particles
+            .par_iter()
+            .map(|particle| {
+                let (u, v) = particle.velocity;
+                let squared_speed = u * u + v * v;
+                StepDiagnostics {
+                    smoke_mass: mass / rest_density,
+                    kinetic_energy: half * mass * squared_speed,
+                    max_speed: squared_speed.sqrt(),
+                    ..StepDiagnostics::default()
+                }
+            })
+            .reduce(StepDiagnostics::default, StepDiagnostics::combine);
+
+        // the particles carry the volume and the energy, the cells only add the derivatives
+        let cells = cell_diagnostics(&self.velocity_grid, &self.config, |x, y| {
+            (self.weights[(x, y)] > T::ZERO).then_some((T::ZERO, T::ZERO))
+        });
+        particles.combine(cells)
+    }
+}
+
+impl<T: Scalar, L: Layout> Solver<T, L> for Sph<T, L> {
+    fn name(&self) -> &'static str {
+        "SPH"
+    }
+
+    fn next_step(&mut self) -> StepDiagnostics<T> {
+        let delta = T::from_f64(self.last_instant.elapsed().as_secs_f64());
+        let max_substep = self.max_substep();
+        let substeps = ((delta / max_substep).floor().to_usize() + 1).min(MAX_SUBSTEPS);
+        let substep = (delta / T::from_usize(substeps)).min(max_substep);
+        for _ in 0..substeps {
+            self.substep(substep);
+        }
+        self.update_fields();
+        self.last_instant = Instant::now();
+        self.diagnostics()
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.config.fit_domain(width);
+        let zeros = Grid::new(width, height, T::ZERO);
+        self.pressure_grid = zeros.clone();
+        self.weights = zeros;
+        self.smoke_grid = Grid::new(width, height, T::ONE);
+        self.velocity_grid = Grid::new(width, height, (T::ZERO, T::ZERO));
+        self.block_grid.resize(width, height, false);
+        self.width = width;
+        self.height = height;
+        self.last_instant = Instant::now();
+        self.densities.clear();
+        self.pressures.clear();
+        self.accelerations.clear();
+        self.fill_water();
+    }
+
+    fn restart_sim(&mut self) {
+        self.resize(self.width, self.height);
+    }
+
+    fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn get_config(&self) -> &SimConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, mut config: SimConfig) {
+        config.fit_domain(self.width);
+        self.config = config;
+    }
+
+    fn get_block_grid(&self) -> &Grid<bool, L> {
+        &self.block_grid
+    }
+
+    /// the particles inside the new block are removed so none get stuck in it
+    fn set_block(&mut self, x: usize, y: usize) {
+        let Some(cell) = self.block_grid.get_mut(x, y) else {
+            return;
+        };
+        *cell = true;
+
+        let h = T::from_f32(self.config.cell_size);
+        self.particles.retain(|particle| {
+            let (particle_x, particle_y) = particle.position;
+            ((particle_x / h).to_usize(), (particle_y / h).to_usize()) != (x, y)
+        });
+        self.densities.clear();
+        self.pressures.clear();
+        self.accelerations.clear();
+    }
+
+    fn unset_block(&mut self, x: usize, y: usize) {
+        if let Some(cell) = self.block_grid.get_mut(x, y) {
+            *cell = false;
+        }
+    }
+
+    fn get_pressure_grid(&self) -> &Grid<T, L> {
+        &self.pressure_grid
+    }
+
+    fn get_smoke_grid(&self) -> &Grid<T, L> {
+        &self.smoke_grid
+    }
+
+    fn velocity_at(&self, x: T, y: T) -> (T, T) {
+        cell_velocity_at(
+            &self.velocity_grid,
+            (x, y),
+            T::from_f32(self.config.cell_size),
+        )
+    }
+
+    fn shading(&self) -> Shading {
+        Shading::Liquid
+    }
+
+    /// drops a small disk of particles on the cell, which splashes when it lands
+    fn add_droplet(&mut self, x: usize, y: usize) {
+        const RADIUS: isize = 2;
+        let h = T::from_f32(self.config.cell_size);
+        let spacing = self.spacing();
+        let half = T::from_f32(0.5);
+        let side = RADIUS * PARTICLES_PER_SIDE as isize;
+
+        for i in -side..side {
+            for j in -side..side {
+                let (offset_x, offset_y) = (
+                    (T::from_f32(i as f32) + half) * spacing,
+                    (T::from_f32(j as f32) + half) * spacing,
+                );
+                let (center_x, center_y) =
+                    ((T::from_usize(x) + half) * h, (T::from_usize(y) + half) * h);
+                let position = (center_x + offset_x, center_y + offset_y);
+                let radius = T::from_f32(RADIUS as f32) * h;
+                let cell = ((position.0 / h).to_usize(), (position.1 / h).to_usize());
+                let is_free = position.0 >= T::ZERO
+                    && position.1 >= T::ZERO
+                    && self.block_grid.contains(cell.0, cell.1)
+                    && !is_solid(&self.block_grid, cell.0, cell.1);
+                if is_free && offset_x * offset_x + offset_y * offset_y < radius * radius {
+                    self.particles.push(LiquidParticle {
+                        position,
+                        velocity: (T::ZERO, T::ZERO),
+                    });
+                }
+            }
+        }
+        self.update_fields();
+    }
+}
+
+/// a hash of the cells of a grid as large as the kernel radius, to find the particles
+/// around a position without a grid covering the whole tank
+#[derive(Clone, Debug, Default)]
+struct SpatialHash<T: Scalar> {
+    /// where the particles of every hash start, with the end of the last one after it
+    starts: Vec<usize>,
+
+    /// the cell of every particle, to skip the particles of other cells with the same hash
+    cells: Vec<(isize, isize)>,
+
+    /// the particles are sorted into it before being swapped
+    buffer: Vec<LiquidParticle<T>>,
+    cell_buffer: Vec<(isize, isize)>,
+}
+
+impl<T: Scalar> SpatialHash<T> {
+    #[inline]
+    fn cell((x, y): (T, T), cell_size: T) -> (isize, isize) {
+        (
+            (x / cell_size).floor().to_f32() as isize,
+            (y / cell_size).floor().to_f32() as isize,
+        )
+    }
+
+    #[inline]
+    fn hash(&self, (x, y): (isize, isize)) -> usize {
+        let hash = x.wrapping_mul(92_837_111) ^ y.wrapping_mul(689_287_499);
+        // the size of the table is a power of two
+        hash.unsigned_abs() & (self.starts.len() - 2)
+    }
+
+    /// sorts the particles by the hash of their cell with a counting sort,
+    /// so the particles of a cell are next to each other in memory
+    fn sort(&mut self, particles: &mut Vec<LiquidParticle<T>>, cell_size: T) {
+        let table_size = (2 * particles.len()).next_power_of_two();
+        self.starts.clear();
+        self.starts.resize(table_size + 1, 0);
+        self.cell_buffer.clear();
+        self.cell_buffer.extend(
+            particles
+                .iter()
+                .map(|particle| Self::cell(particle.position, cell_size)),
+        );
+
+        for &cell in &self.cell_buffer {
+            let hash = self.hash(cell);
+            self.starts[hash] += 1;
+        }
+        let mut start = 0;
+        for count in &mut self.starts {
+            start += *count;
+            *count = start;
+        }
+        // every start now points at the end of its hash and moves back as it is filled
+        self.buffer.clear();
+        self.buffer
+            .resize(particles.len(), LiquidParticle::default());
+        self.cells.clear();
+        self.cells.resize(particles.len(), (0, 0));
+        for (particle, &cell) in particles.iter().zip(&self.cell_buffer) {
+            let hash = self.hash(cell);
+            self.starts[hash] -= 1;
+            self.buffer[self.starts[hash]] = *particle;
+            self.cells[self.starts[hash]] = cell;
+        }
+        std::mem::swap(particles, &mut self.buffer);
+    }
+
+    /// calls `f` with the index of every particle in the cells around the position,
+    /// which holds all the particles closer than `cell_size`
+    fn for_each_neighbor(&self, position: (T, T), cell_size: T, mut f: impl FnMut(usize)) {
+        let (x, y) = Self::cell(position, cell_size);
+        for cell in (x - 1..=x + 1).flat_map(|x| (y - 1..=y + 1).map(move |y| (x, y))) {
+            let hash = self.hash(cell);
+            for index in self.starts[hash]..self.starts[hash + 1] {
+                if self.cells[index] == cell {
+                    f(index);
+                }
+            }
+        }
+    }
+}
+
+/// the poly6 kernel in 2d, smooth at the center to sum the densities
+#[inline]
+fn density_kernel<T: Scalar>(squared_distance: T, radius: T) -> T {
+    let squared_radius = radius * radius;
+    if squared_distance >= squared_radius {
+        return T::ZERO;
+    }
+    let difference = squared_radius - squared_distance;
+    let radius_4 = squared_radius * squared_radius;
+    T::from_f32(4.0 / PI) / (radius_4 * radius_4) * difference * difference * difference
+}
+
+/// the derivative of the spiky kernel in 2d, its slope does not vanish at the center
+/// so the particles that get close are pushed apart
+#[inline]
+fn pressure_kernel_slope<T: Scalar>(distance: T, radius: T) -> T {
+    if distance >= radius {
+        return T::ZERO;
+    }
+    let difference = radius - distance;
+    let radius_5 = radius * radius * radius * radius * radius;
+    -T::from_f32(30.0 / PI) / radius_5 * difference * difference
+}
+
+/// the laplacian of the viscosity kernel in 2d, positive everywhere in the radius
+#[inline]
+fn viscosity_kernel_laplacian<T: Scalar>(distance: T, radius: T) -> T {
+    if distance >= radius {
+        return T::ZERO;
+    }
+    let radius_5 = radius * radius * radius * radius * radius;
+    T::from_f32(40.0 / PI) / radius_5 * (radius - distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// particles spread over `[-0.5, 1.5)` by a linear congruential generator
+    fn scattered_particles(count: usize) -> Vec<LiquidParticle<f64>> {
+        let mut state = 12_345_u64;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 11) as f64 / (1_u64 << 53) as f64 * 2.0 - 0.5
+        };
+        (0..count)
+            .map(|_| LiquidParticle {
+                position: (next(), next()),
+                velocity: (0.0, 0.0),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_neighbors_match_a_brute_force_search() {
+        let radius = 0.05;
+        let mut particles = scattered_particles(2000);
+        let mut hash = SpatialHash::default();
+        hash.sort(&mut particles, radius);
+
+        let distance = |(ax, ay): (f64, f64), (bx, by): (f64, f64)| (ax - bx).hypot(ay - by);
+        for particle in particles.iter().step_by(7) {
+            let position = particle.position;
+            let mut found = Vec::new();
+            hash.for_each_neighbor(position, radius, |index| found.push(index));
+            // no particle is given twice, even when two of the cells share a hash
+            let count = found.len();
+            found.sort_unstable();
+            found.dedup();
+            assert_eq!(found.len(), count);
+
+            found.retain(|&index| distance(particles[index].position, position) < radius);
+            let close: Vec<_> = (0..particles.len())
+                .filter(|&index| distance(particles[index].position, position) < radius)
+                .collect();
+            assert_eq!(found, close);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{Grid, Layout, Scalar};
+
+/// the running mean and root mean square of the fluctuations of a field,
+/// updated with Welford's algorithm so long runs do not lose precision
+#[derive(Clone, Debug, Default)]
+pub struct RunningStatistics<T: Scalar, L: Layout> {
+    mean: Grid<T, L>,
+
+    /// the sum of the squared deviations from the mean
+    squared_deviations: Grid<T, L>,
+
+    samples: usize,
+}
+
+impl<T: Scalar, L: Layout> RunningStatistics<T, L> {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            mean: Grid::new(width, height, T::ZERO),
+            squared_deviations: Grid::new(width, height, T::ZERO),
+            samples: 0,
+        }
+    }
+
+    /// adds the value of every cell, in storage order
+    fn add(&mut self, values: impl Iterator<Item = T>) {
+        self.samples += 1;
+        let samples = T::from_usize(self.samples);
+        let cells = self.mean.iter_mut().zip(self.squared_deviations.iter_mut());
+        for ((mean, squared_deviation), value) in cells.zip(values) {
+            let deviation = value - *mean;
+            *mean += deviation / samples;
+            *squared_deviation += deviation * (value - *mean);
+        }
+    }
+
+    #[inline]
+    pub fn mean(&self) -> &Grid<T, L> {
+        &self.mean
+    }
+
+    /// the root mean square of the difference between the samples and the mean of a cell
+    pub fn rms_at(&self, x: usize, y: usize) -> T {
+        if self.samples == 0 {
+            return T::ZERO;
+        }
+        (self.squared_deviations[(x, y)] / T::from_usize(self.samples)).sqrt()
+    }
+
+    /// computes the root mean square of the fluctuations of every cell
+    pub fn rms(&self) -> Grid<T, L> {
+        let (width, height) = self.mean.size();
+        Grid::from_fn(width, height, |x, y| self.rms_at(x, y))
+    }
+}
+
+/// the statistics of the fields of a sim since they were last reset,
+/// see [`FluidSim::enable_statistics`](crate::FluidSim::enable_statistics)
+#[derive(Clone, Debug, Default)]
+pub struct FieldStatistics<T: Scalar, L: Layout> {
+    /// the horizontal speed at the center of the cells
+    pub horizontal_speed: RunningStatistics<T, L>,
+
+    /// the vertical speed at the center of the cells
+    pub vertical_speed: RunningStatistics<T, L>,
+
+    pub pressure: RunningStatistics<T, L>,
+    pub smoke: RunningStatistics<T, L>,
+}
+
+impl<T: Scalar, L: Layout> FieldStatistics<T, L> {
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        Self {
+            horizontal_speed: RunningStatistics::new(width, height),
+            vertical_speed: RunningStatistics::new(width, height),
+            pressure: RunningStatistics::new(width, height),
+            smoke: RunningStatistics::new(width, height),
+        }
+    }
+
+    /// the number of steps that have been accumulated
+    #[inline]
+    pub fn sample_count(&self) -> usize {
+        self.pressure.samples
+    }
+
+    /// adds the fields of a step, the velocities are in the storage order of the grids
+    pub(crate) fn add(
+        &mut self,
+        velocities: impl Iterator<Item = (T, T)> + Clone,
+        pressure: &Grid<T, L>,
+        smoke: &Grid<T, L>,
+    ) {
+        self.horizontal_speed
+            .add(velocities.clone().map(|(horizontal, _)| horizontal));
+        self.vertical_speed
+            .add(velocities.map(|(_, vertical)| vertical));
+        self.pressure.add(pressure.iter().copied());
+        self.smoke.add(smoke.iter().copied());
+    }
+}
@@ -0,0 +1,108 @@
+use rayon::prelude::*;
+
+use crate::{FluidSim, Layout, Scalar};
+
+/// how [`FluidSim::streamlines`] seeds and traces the streamlines
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StreamlineSettings<T: Scalar = f32> {
+    /// the number of cells between two seeds in both directions
+    pub seed_spacing: usize,
+
+    /// the distance between two points of a streamline as a fraction of a cell
+    pub step_length: T,
+
+    /// the largest number of steps traced in each direction from a seed
+    pub max_steps: usize,
+}
+
+impl<T: Scalar> Default for StreamlineSettings<T> {
+    fn default() -> Self {
+        Self {
+            seed_spacing: 8,
+            step_length: T::from_f32(0.5),
+            max_steps: 400,
+        }
+    }
+}
+
+/// a curve that is tangent to the velocity field at one instant
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Streamline<T: Scalar = f32> {
+    /// the position in meters the streamline was traced from
+    pub seed: (T, T),
+
+    /// the positions in meters from the upstream end to the downstream end
+    pub points: Vec<(T, T)>,
+}
+
+impl<T: Scalar, L: Layout> FluidSim<T, L> {
+    /// traces the streamlines going through evenly spaced seeds in the fluid cells
+    ///
+    /// every streamline is traced upstream and downstream of its seed until it reaches a block,
+    /// leaves the domain, stops at a stagnation point or takes `max_steps` steps
+    pub fn streamlines(&self, settings: &StreamlineSettings<T>) -> Vec<Streamline<T>> {
+        let (width, height) = self.get_size();
+        let spacing = settings.seed_spacing.max(1);
+        let h = T::from_f32(self.get_config().cell_size);
+
+        // the seeds are centered in the domain
+        let seeds: Vec<(T, T)> = (spacing / 2..width)
+            .step_by(spacing)
+            .flat_map(|x| (spacing / 2..height).step_by(spacing).map(move |y| (x, y)))
+            .filter(|&cell| !self.get_block_grid()[cell])
+            .map(|(x, y)| {
+                let half = T::from_f32(0.5);
+                ((T::from_usize(x) + half) * h, (T::from_usize(y) + half) * h)
+            })
+            .collect();
+
+        seeds
+            .into_par_iter()
+            .map(|seed| {
+                let mut points = self.trace_streamline(seed, -T::ONE, settings);
+                points.reverse();
+                points.push(seed);
+                points.extend(self.trace_streamline(seed, T::ONE, settings));
+                Streamline { seed, points }
+            })
+            .collect()
+    }
+
+    /// follows the direction of the velocity from a seed, backwards if `direction` is -1,
+    /// and returns the points after the seed
+    fn trace_streamline(
+        &self,
+        seed: (T, T),
+        direction: T,
+        settings: &StreamlineSettings<T>,
+    ) -> Vec<(T, T)> {
+        let step = direction * settings.step_length * T::from_f32(self.get_config().cell_size);
+        let half = T::from_f32(0.5);
+
+        // the unit vector along the velocity, the speed does not change the length of the steps
+        let tangent = |(x, y): (T, T)| {
+            let (u, v) = self.velocity_at(x, y);
+            let speed = (u * u + v * v).sqrt();
+            (speed > T::from_f32(1e-6)).then(|| (u / speed, v / speed))
+        };
+
+        let mut points = Vec::new();
+        let (mut x, mut y) = seed;
+        for _ in 0..settings.max_steps {
+            // midpoint method
+            let Some((u1, v1)) = tangent((x, y)) else {
+                break;
+            };
+            let Some((u2, v2)) = tangent((x + half * step * u1, y + half * step * v1)) else {
+                break;
+            };
+            let (next_x, next_y) = (x + step * u2, y + step * v2);
+            if self.cell_at(next_x, next_y).is_none() || self.is_block_at(next_x, next_y) {
+                break;
+            }
+            (x, y) = (next_x, next_y);
+            points.push((x, y));
+        }
+        points
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+
+use rayon::prelude::*;
+
+use crate::{FluidSim, Layout, Scalar};
+
+/// how the tracers are moved through the velocity field
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Integrator {
+    /// the midpoint method, samples the velocity twice per step
+    #[default]
+    Rk2,
+
+    /// the classic Runge-Kutta method, samples the velocity four times per step
+    Rk4,
+}
+
+/// what happens to a tracer that would move into a block
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WallBehavior {
+    /// the tracer is reflected on the face of the block
+    #[default]
+    Bounce,
+
+    /// the tracer stops in front of the block for the rest of its life
+    Stick,
+}
+
+/// a massless particle that follows the fluid
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tracer<T: Scalar = f32> {
+    /// the position in meters from the bottom left corner
+    pub position: (T, T),
+
+    /// the time in seconds since the tracer was added
+    pub age: T,
+
+    /// true once the tracer stuck to a block, see [`WallBehavior::Stick`]
+    pub is_stuck: bool,
+}
+
+impl<T: Scalar> Tracer<T> {
+    pub fn new(position: (T, T)) -> Self {
+        Self {
+            position,
+            age: T::ZERO,
+            is_stuck: false,
+        }
+    }
+}
+
+/// a point that continuously adds tracers, which draws the streakline going through it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Emitter<T: Scalar = f32> {
+    /// the position in meters from the bottom left corner
+    pub position: (T, T),
+
+    /// the number of tracers added per second
+    pub rate: T,
+
+    /// the part of a tracer that has not been added yet
+    pending: T,
+}
+
+impl<T: Scalar> Emitter<T> {
+    pub fn new(position: (T, T), rate: T) -> Self {
+        Self {
+            position,
+            rate,
+            pending: T::ZERO,
+        }
+    }
+}
+
+/// the tracers of a sim and the emitters adding them
+///
+/// the tracers that leave the domain are removed, and the oldest ones once there are
+/// more than `max_tracers`
+#[derive(Clone, Debug)]
+pub struct Tracers<T: Scalar = f32> {
+    tracers: VecDeque<Tracer<T>>,
+    emitters: Vec<Emitter<T>>,
+    pub integrator: Integrator,
+    pub wall_behavior: WallBehavior,
+    pub max_tracers: usize,
+}
+
+impl<T: Scalar> Default for Tracers<T> {
+    fn default() -> Self {
+        Self {
+            tracers: VecDeque::new(),
+            emitters: Vec::new(),
+            integrator: Integrator::default(),
+            wall_behavior: WallBehavior::default(),
+            max_tracers: 5000,
+        }
+    }
+}
+
+impl<T: Scalar> Tracers<T> {
+    /// the tracers from the oldest to the newest
+    #[inline]
+    pub fn tracers(&self) -> &VecDeque<Tracer<T>> {
+        &self.tracers
+    }
+
+    #[inline]
+    pub fn emitters(&self) -> &[Emitter<T>] {
+        &self.emitters
+    }
+
+    pub fn add_tracer(&mut self, tracer: Tracer<T>) {
+        self.tracers.push_back(tracer);
+        self.remove_oldest();
+    }
+
+    pub fn add_emitter(&mut self, emitter: Emitter<T>) {
+        self.emitters.push(emitter);
+    }
+
+    pub fn remove_emitter(&mut self, index: usize) -> Emitter<T> {
+        self.emitters.remove(index)
+    }
+
+    pub fn clear_tracers(&mut self) {
+        self.tracers.clear();
+    }
+
+    pub fn clear_emitters(&mut self) {
+        self.emitters.clear();
+    }
+
+    /// removes every tracer and the emitters outside of a domain of the given size in meters
+    pub(crate) fn restart(&mut self, (width, height): (T, T)) {
+        self.tracers.clear();
+        self.emitters.retain(|emitter| {
+            let (x, y) = emitter.position;
+            x >= T::ZERO && y >= T::ZERO && x < width && y < height
+        });
+    }
+
+    fn remove_oldest(&mut self) {
+        let extra = self.tracers.len().saturating_sub(self.max_tracers);
+        self.tracers.drain(..extra);
+    }
+
+    /// adds the tracers of the emitters and moves every tracer with the velocities of the sim
+    pub(crate) fn advance<L: Layout>(&mut self, sim: &FluidSim<T, L>, delta: T) {
+        for emitter in &mut self.emitters {
+            emitter.pending += emitter.rate * delta;
+            while emitter.pending >= T::ONE {
+                emitter.pending -= T::ONE;
+                self.tracers.push_back(Tracer::new(emitter.position));
+            }
+        }
+
+        let (integrator, wall_behavior) = (self.integrator, self.wall_behavior);
+        self.tracers.par_iter_mut().for_each(|tracer| {
+            tracer.age += delta;
+            if tracer.is_stuck {
+                return;
+            }
+            let target = Self::integrate(sim, integrator, tracer.position, delta);
+            Self::move_tracer(sim, wall_behavior, tracer, target);
+        });
+
+        self.tracers
+            .retain(|tracer| sim.cell_at(tracer.position.0, tracer.position.1).is_some());
+        self.remove_oldest();
+    }
+
+    /// returns where the velocity field takes a position in `delta` seconds
+    fn integrate<L: Layout>(
+        sim: &FluidSim<T, L>,
+        integrator: Integrator,
+        (x, y): (T, T),
+        delta: T,
+    ) -> (T, T) {
+        let half = T::from_f32(0.5);
+        let velocity = |(x, y): (T, T)| sim.velocity_at(x, y);
+        let (u1, v1) = velocity((x, y));
+        match integrator {
+            Integrator::Rk2 => {
+                let (u2, v2) = velocity((x + half * delta * u1, y + half * delta * v1));
+                (x + delta * u2, y + delta * v2)
+            }
+            Integrator::Rk4 => {
+                let (u2, v2) = velocity((x + half * delta * u1, y + half * delta * v1));
+                let (u3, v3) = velocity((x + half * delta * u2, y + half * delta * v2));
+                let (u4, v4) = velocity((x + delta * u3, y + delta * v3));
+                let sixth = delta / T::from_f32(6.0);
+                let two = T::from_f32(2.0);
+                (
+                    x + sixth * (u1 + two * u2 + two * u3 + u4),
+                    y + sixth * (v1 + two * v2 + two * v3 + v4),
+                )
+            }
+        }
+    }
+
+    /// moves a tracer to its target unless there is a block in the way
+    fn move_tracer<L: Layout>(
+        sim: &FluidSim<T, L>,
+        wall_behavior: WallBehavior,
+        tracer: &mut Tracer<T>,
+        target: (T, T),
+    ) {
+        if !sim.is_block_at(target.0, target.1) {
+            tracer.position = target;
+            return;
+        }
+        if wall_behavior == WallBehavior::Stick {
+            tracer.is_stuck = true;
+            return;
+        }
+
+        // the axes that cross into the block are reflected on the face of the cell
+        let (x, y) = tracer.position;
+        let (mut target_x, mut target_y) = target;
+        if sim.is_block_at(target_x, y) {
+            target_x = x - (target_x - x);
+        }
+        if sim.is_block_at(x, target_y) {
+            target_y = y - (target_y - y);
+        }
+        if !sim.is_block_at(target_x, target_y) {
+            tracer.position = (target_x, target_y);
+        }
+    }
+}
@@ -5,16 +5,27 @@ use ratatui::{
     widgets::{Block, Paragraph, Widget, Wrap},
 };
 
-use terminal_fluid_sim::FluidSim;
+use terminal_fluid_sim::{
+    render::{render_heat_sources, render_probes, render_tracers},
+    Grid, Solver,
+};
+
+use crate::app::{App, EditorTool, SelectedObstacle};
+
+use super::{
+    view::{HalfBlock, SimView},
+    THEME,
+};
 
-use crate::app::App;
+pub fn render_editor_info(tool: EditorTool, area: Rect, buf: &mut Buffer) {
+    let info = format!(
+        r#"Use the mouse and keyboard to edit your craft!
 
-use super::THEME;
+Tool: {}
 
-pub fn render_editor_info(area: Rect, buf: &mut Buffer) {
-    let info = r#"Use the mouse and keyboard to edit your craft!
-        
-(hint: controls at the bottom)"#;
+(hint: controls at the bottom)"#,
+        tool.name()
+    );
     Paragraph::new(info)
         .style(THEME.text)
         .block(
@@ -29,53 +40,67 @@ pub fn render_editor_info(area: Rect, buf: &mut Buffer) {
 
 pub struct Editor<'a> {
     mouse_pos: Option<(u16, u16)>,
-    blocks: &'a Vec<bool>,
+    selected_obstacle: Option<&'a SelectedObstacle>,
+    sim: &'a dyn Solver,
 }
 
 impl Widget for Editor<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        // the rendering height is half the sim height
-        let height = (area.height * 2) as usize;
+        let view = SimView::new(area, self.sim.get_size());
+        let blocks = self.sim.get_block_grid();
+        let is_block = |position| blocks[position];
+
+        let (width, height) = self.sim.get_size();
+        let mut selected: Grid<bool> = Grid::new(width, height, false);
+        if let Some(obstacle) = self.selected_obstacle {
+            for &position in &obstacle.blocks {
+                selected[position] = true;
+            }
+        }
+        let block_color = |position| {
+            if selected[position] {
+                THEME.selected_blocks
+            } else {
+                THEME.sim_blocks
+            }
+        };
 
-        for (x_index, x_pos) in (area.left()..area.right()).enumerate() {
-            let mut y_index = height;
+        for x_pos in area.left()..area.right() {
             for y_pos in area.top()..area.bottom() {
-                y_index -= 1;
-                let up_index = FluidSim::calculate_index_with_height(height, x_index, y_index);
-
-                y_index -= 1;
-                let down_index = FluidSim::calculate_index_with_height(height, x_index, y_index);
+                let up = view.to_sim(x_pos, y_pos, HalfBlock::Upper);
+                let down = view.to_sim(x_pos, y_pos, HalfBlock::Lower);
+                let (up_is_block, down_is_block) = (is_block(up), is_block(down));
 
                 // if there are not blocks the skip
-                if !(self.blocks[up_index] || self.blocks[down_index]) {
+                if !(up_is_block || down_is_block) {
                     continue;
                 }
 
                 // the top block is the foreground and the background is the bottom
                 let cell = buf.get_mut(x_pos, y_pos).set_char('▄');
 
-                let block_color = THEME.sim_blocks;
-
-                if self.blocks[up_index] {
-                    cell.set_bg(block_color);
-                } else {
-                    // set the color to the background to make it as if there was no block
-                    // cell.set_fg(THEME.background.bg.unwrap());
+                if up_is_block {
+                    cell.set_bg(block_color(up));
                 }
 
-                if self.blocks[down_index] {
-                    cell.set_fg(block_color);
+                if down_is_block {
+                    cell.set_fg(block_color(down));
                 } else {
                     cell.set_fg(THEME.background.bg.unwrap());
                 }
             }
         }
 
+        if let Some(sim) = self.sim.as_fluid_sim() {
+            if sim.simulates_heat() {
+                render_heat_sources(sim, &view, buf);
+            }
+            render_tracers(sim, &view, buf);
+            render_probes(sim, &view, buf);
+        }
+
         if let Some(mouse_pos) = self.mouse_pos {
-            // get x and y in simulator coordinates
-            let (x, y) = editor_area_to_sim_coordinates(mouse_pos, &area);
-            let down_index = FluidSim::calculate_index_with_height(height, x, y);
-            let down_is_block = self.blocks[down_index];
+            let down_is_block = is_block(view.to_sim(mouse_pos.0, mouse_pos.1, HalfBlock::Lower));
             hover_mouse(mouse_pos, down_is_block, buf);
         }
     }
@@ -84,8 +109,9 @@ impl Widget for Editor<'_> {
 pub fn render_editor(app: &mut App, area: Rect, buf: &mut Buffer) {
     // editor
     Editor {
-        blocks: app.fluid_sim.get_block_grid(),
+        sim: app.sim.as_ref(),
         mouse_pos: app.editor_info.last_mouse_pos,
+        selected_obstacle: app.editor_info.selected_obstacle.as_ref(),
     }
     .render(area, buf);
 }
@@ -102,28 +128,3 @@ fn hover_mouse(mouse_pos: (u16, u16), down_is_block: bool, buf: &mut Buffer) {
         cell.set_char('▄');
     }
 }
-
-/// returns the block that the mouse coordinate is pointing to
-/// in simulation coordinates according to the area of the editor
-/// returns the BOTTOM block
-/// ex:
-///
-/// x is the position of the mouse starting from top left as the origin
-/// EO is the origin of the editor increasing in y towards the bottom
-/// SO is the origin of the simulation increasing in y towards the top
-///
-///       _________________
-///      |     (EO)_______|
-///      |        |      ||
-///      |        |  x   ||
-///      |    (SO)‾‾‾‾‾‾‾ |
-///      ‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾
-pub fn editor_area_to_sim_coordinates(
-    mouse_coordinates: (u16, u16),
-    editor_area: &Rect,
-) -> (usize, usize) {
-    (
-        (mouse_coordinates.0 - editor_area.x) as usize,
-        2 * (editor_area.height - mouse_coordinates.1) as usize,
-    )
-}
@@ -2,25 +2,42 @@ use layout::Flex;
 use ratatui::{layout::Constraint::*, prelude::*, widgets::Block};
 use style::Styled;
 
-use crate::app::{App, AppState};
-
-use super::{
-    editor::{render_editor, render_editor_info},
-    sim_renderer::{render_sim, render_sim_info},
-    theme::THEME,
+use terminal_fluid_sim::{
+    render::{render_sim, render_streamlines, RenderMode, THEME},
+    FluidSim,
 };
 
+use crate::app::{App, AppConfig, AppInfo, AppState, DiagnosticsHistory, EditorTool, SolverKind};
+
+use super::editor::{render_editor, render_editor_info};
+
 pub fn render_app(app: &mut App, area: Rect, buf: &mut Buffer) -> Rect {
-    let [info_area, sim_area] = set_layout(&app.state, area, buf);
+    let solver = app.config.get_solver();
+    let [info_area, sim_area] = set_layout(&app.state, app.editor_info.tool, solver, area, buf);
 
     let border = Block::bordered().style(THEME.borders);
     let inner_sim_area = border.inner(sim_area);
 
     match app.state {
         AppState::Running => {
-            border.render(sim_area, buf);
-            render_sim_info(&app.info, &mut app.config, info_area, buf);
-            render_sim(&mut app.fluid_sim, inner_sim_area, buf);
+            let samples = app
+                .sim
+                .as_fluid_sim()
+                .and_then(FluidSim::get_statistics)
+                .map_or(0, |statistics| statistics.sample_count());
+            match app.render_mode {
+                RenderMode::Instant => border,
+                mode => border
+                    .title(format!(" {} of {samples} steps ", mode.name()))
+                    .title_style(THEME.tab_text)
+                    .title_alignment(Alignment::Center),
+            }
+            .render(sim_area, buf);
+            render_sim_info(&app.info, &app.diagnostics, &mut app.config, info_area, buf);
+            render_sim(app.sim.as_ref(), app.render_mode, inner_sim_area, buf);
+            if let Some(sim) = app.sim.as_fluid_sim().filter(|_| app.show_streamlines) {
+                render_streamlines(sim, inner_sim_area, buf);
+            }
         }
         AppState::Editing => {
             border
@@ -28,7 +45,7 @@ pub fn render_app(app: &mut App, area: Rect, buf: &mut Buffer) -> Rect {
                 .title_style(THEME.tab_text)
                 .title_alignment(Alignment::Center)
                 .render(sim_area, buf);
-            render_editor_info(info_area, buf);
+            render_editor_info(app.editor_info.tool, info_area, buf);
             render_editor(app, inner_sim_area, buf);
         }
         _ => {}
@@ -36,7 +53,13 @@ pub fn render_app(app: &mut App, area: Rect, buf: &mut Buffer) -> Rect {
     inner_sim_area
 }
 
-fn set_layout(state: &AppState, area: Rect, buf: &mut Buffer) -> [Rect; 2] {
+fn set_layout(
+    state: &AppState,
+    tool: EditorTool,
+    solver: SolverKind,
+    area: Rect,
+    buf: &mut Buffer,
+) -> [Rect; 2] {
     // make the bottom controls have height of 1 and the fill the rest
     let [main_area, controls_area] = Layout::vertical([Fill(1), Length(1)]).areas(area);
 
@@ -47,7 +70,7 @@ fn set_layout(state: &AppState, area: Rect, buf: &mut Buffer) -> [Rect; 2] {
 
     let info_area = render_title(left_area, buf);
 
-    controls_layout(state, controls_area, buf);
+    controls_layout(state, tool, solver, controls_area, buf);
 
     [info_area, sim_area]
 }
@@ -68,22 +91,57 @@ fn render_title(area: Rect, buf: &mut Buffer) -> Rect {
     info_area
 }
 
-fn controls_layout(state: &AppState, area: Rect, buf: &mut Buffer) {
+fn controls_layout(
+    state: &AppState,
+    tool: EditorTool,
+    solver: SolverKind,
+    area: Rect,
+    buf: &mut Buffer,
+) {
     let mut controls = vec![("q", "quit")];
 
     let mut additional = match state {
-        AppState::Running => vec![
-            ("tab", "switch to editor"),
-            ("↑", "up selection"),
-            ("←", "reduce value"),
-            ("→", "increase value"),
-            ("↓", "down selection"),
-        ],
-        AppState::Editing => vec![
-            ("tab", "switch to simulation"),
-            ("left click", "add block"),
-            ("right click", "remove block"),
-        ],
+        AppState::Running => {
+            let mut controls = vec![
+                ("tab", "switch to editor"),
+                ("↑", "up selection"),
+                ("←", "reduce value"),
+                ("→", "increase value"),
+                ("↓", "down selection"),
+                ("m", "switch view"),
+                ("r", "reset averages"),
+                ("s", "streamlines"),
+                ("e", "export probes"),
+            ];
+            if matches!(solver, SolverKind::ShallowWater | SolverKind::Sph) {
+                controls.push(("left click", "drop water"));
+            }
+            controls
+        }
+        AppState::Editing => {
+            let mut controls = vec![("tab", "switch to simulation"), ("t", "switch tool")];
+            controls.extend(match tool {
+                EditorTool::Draw => [("left click", "add block"), ("right click", "remove block")],
+                EditorTool::Select => [
+                    ("left click", "select obstacle"),
+                    ("right click", "clear selection"),
+                ],
+                EditorTool::Probe => [("left click", "add probe"), ("right click", "remove probe")],
+                EditorTool::Emitter => [
+                    ("left click", "add emitter"),
+                    ("right click", "remove emitter"),
+                ],
+                EditorTool::Heater => [
+                    ("left click", "add heater"),
+                    ("right click", "remove heater"),
+                ],
+                EditorTool::Cooler => [
+                    ("left click", "add cooler"),
+                    ("right click", "remove cooler"),
+                ],
+            });
+            controls
+        }
         _ => Vec::with_capacity(0),
     };
     controls.append(&mut additional);
@@ -105,6 +163,25 @@ fn controls_layout(state: &AppState, area: Rect, buf: &mut Buffer) {
 }
 
 /// renders a border over the area with a title and returns the inner area
+pub fn render_sim_info(
+    info: &AppInfo,
+    diagnostics: &DiagnosticsHistory,
+    config: &mut AppConfig,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    // the diagnostics have two rows for each of their 5 values and a border
+    let [up, middle, down] = Layout::vertical([Fill(1), Length(12), Fill(1)]).areas(area);
+
+    let info_area = render_border_with_title("Sim Info", up, buf);
+    let diagnostics_area = render_border_with_title("Diagnostics", middle, buf);
+    let config_area = render_border_with_title("Settings", down, buf);
+
+    info.render(info_area, buf);
+    diagnostics.render(diagnostics_area, buf);
+    config.render(config_area, buf);
+}
+
 pub fn render_border_with_title(title: &str, area: Rect, buf: &mut Buffer) -> Rect {
     let border = Block::bordered().set_style(THEME.borders).title(title);
     let inner = border.inner(area);
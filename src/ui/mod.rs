@@ -1,7 +1,5 @@
 pub mod editor;
 mod layout;
-mod sim_renderer;
-mod theme;
 
 pub use layout::*;
-pub use theme::THEME;
+pub use terminal_fluid_sim::render::{view, RenderMode, THEME};